@@ -1,6 +1,8 @@
 use crate::db::{ConnectionConfig, DatabaseConnection};
 use crate::error::AppError;
 use crate::state::AppState;
+use crate::types::ActiveConnection;
+use std::sync::Arc;
 use tauri::State;
 
 #[tauri::command]
@@ -9,39 +11,70 @@ pub async fn test_connection(config: ConnectionConfig) -> Result<bool, AppError>
     Ok(true)
 }
 
+/// Opens a connection and registers it under `config.id`.
+///
+/// Connecting (including any SSH handshake) happens without holding the
+/// registry lock, so a slow host doesn't block commands on other
+/// connections.
 #[tauri::command]
 pub async fn connect(
     config: ConnectionConfig,
     state: State<'_, AppState>,
 ) -> Result<String, AppError> {
-    let mut connection = state.connection.lock().await;
-
-    if connection.is_some() {
-        return Err(AppError::AlreadyConnected);
+    if state.connections.lock().await.contains_key(&config.id) {
+        return Err(AppError::AlreadyConnected(config.id));
     }
 
     let db = DatabaseConnection::connect(config.clone()).await?;
     let connection_id = config.id.clone();
 
-    *connection = Some(db);
+    // Another connect for the same id may have finished in the meantime
+    let duplicate = {
+        let mut connections = state.connections.lock().await;
+        if connections.contains_key(&connection_id) {
+            Some(db)
+        } else {
+            connections.insert(connection_id.clone(), Arc::new(db));
+            None
+        }
+    };
+    if let Some(db) = duplicate {
+        db.disconnect().await;
+        return Err(AppError::AlreadyConnected(connection_id));
+    }
 
     Ok(connection_id)
 }
 
 #[tauri::command]
-pub async fn disconnect(state: State<'_, AppState>) -> Result<(), AppError> {
-    let mut connection = state.connection.lock().await;
+pub async fn disconnect(connection_id: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    let db = state.connections.lock().await.remove(&connection_id);
 
-    if let Some(db) = connection.take() {
-        db.pool.close().await;
+    if let Some(db) = db {
+        db.disconnect().await;
     }
+    state.invalidate_schema(&connection_id).await;
 
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_connection_status(state: State<'_, AppState>) -> Result<Option<String>, AppError> {
-    let connection = state.connection.lock().await;
+pub async fn get_connection_status(
+    state: State<'_, AppState>,
+) -> Result<Vec<ActiveConnection>, AppError> {
+    let connections = state.connections.lock().await;
+
+    let mut active: Vec<ActiveConnection> = connections
+        .values()
+        .map(|db| ActiveConnection {
+            id: db.config.id.clone(),
+            name: db.config.name.clone(),
+            host: db.config.host.clone(),
+            port: db.config.port,
+            database: db.config.database.clone(),
+        })
+        .collect();
+    active.sort_by(|a, b| a.name.cmp(&b.name));
 
-    Ok(connection.as_ref().map(|db| db.config.name.clone()))
+    Ok(active)
 }
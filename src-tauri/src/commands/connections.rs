@@ -1,5 +1,5 @@
 use crate::error::AppError;
-use crate::types::{SaveConnectionInput, SavedConnection};
+use crate::types::{SaveConnectionInput, SavedConnection, SshAuthMethod};
 use keyring::Entry;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -56,8 +56,13 @@ fn keyring_account(id: &str) -> String {
     format!("connection-{}", id)
 }
 
-fn save_password_to_keyring(id: &str, password: &str) -> Result<(), AppError> {
-    let entry = Entry::new(KEYRING_SERVICE, &keyring_account(id))
+/// Keyring account holding the SSH password or private key passphrase
+fn ssh_keyring_account(id: &str) -> String {
+    format!("connection-{}-ssh", id)
+}
+
+fn save_password_to_keyring(account: &str, password: &str) -> Result<(), AppError> {
+    let entry = Entry::new(KEYRING_SERVICE, account)
         .map_err(|e| AppError::ConfigError(format!("Failed to create keyring entry: {}", e)))?;
 
     entry
@@ -67,8 +72,8 @@ fn save_password_to_keyring(id: &str, password: &str) -> Result<(), AppError> {
     Ok(())
 }
 
-fn get_password_from_keyring(id: &str) -> Result<Option<String>, AppError> {
-    let entry = Entry::new(KEYRING_SERVICE, &keyring_account(id))
+fn get_password_from_keyring(account: &str) -> Result<Option<String>, AppError> {
+    let entry = Entry::new(KEYRING_SERVICE, account)
         .map_err(|e| AppError::ConfigError(format!("Failed to create keyring entry: {}", e)))?;
 
     match entry.get_password() {
@@ -81,8 +86,8 @@ fn get_password_from_keyring(id: &str) -> Result<Option<String>, AppError> {
     }
 }
 
-fn delete_password_from_keyring(id: &str) -> Result<(), AppError> {
-    let entry = Entry::new(KEYRING_SERVICE, &keyring_account(id))
+fn delete_password_from_keyring(account: &str) -> Result<(), AppError> {
+    let entry = Entry::new(KEYRING_SERVICE, account)
         .map_err(|e| AppError::ConfigError(format!("Failed to create keyring entry: {}", e)))?;
 
     // Ignore error if password doesn't exist
@@ -93,8 +98,18 @@ fn delete_password_from_keyring(id: &str) -> Result<(), AppError> {
 
 #[tauri::command]
 pub async fn save_connection(app: AppHandle, input: SaveConnectionInput) -> Result<(), AppError> {
-    // Save password to keyring
-    save_password_to_keyring(&input.id, &input.password)?;
+    // Save passwords to keyring
+    save_password_to_keyring(&keyring_account(&input.id), &input.password)?;
+    match &input.ssh {
+        Some(ssh) => {
+            let secret = match ssh.auth_method {
+                SshAuthMethod::Password => &ssh.password,
+                SshAuthMethod::PrivateKey => &ssh.passphrase,
+            };
+            save_password_to_keyring(&ssh_keyring_account(&input.id), secret)?;
+        }
+        None => delete_password_from_keyring(&ssh_keyring_account(&input.id))?,
+    }
 
     // Load existing connections
     let mut file = load_connections_file(&app)?;
@@ -115,6 +130,10 @@ pub async fn save_connection(app: AppHandle, input: SaveConnectionInput) -> Resu
         database: input.database,
         username: input.username,
         ssl_mode: input.ssl_mode,
+        ssl_root_cert: input.ssl_root_cert,
+        ssl_cert: input.ssl_cert,
+        ssl_key: input.ssl_key,
+        ssh: input.ssh,
         is_default: input.is_default,
     };
 
@@ -138,8 +157,9 @@ pub async fn load_connections(app: AppHandle) -> Result<Vec<SavedConnection>, Ap
 
 #[tauri::command]
 pub async fn delete_connection(app: AppHandle, id: String) -> Result<(), AppError> {
-    // Delete password from keyring
-    delete_password_from_keyring(&id)?;
+    // Delete passwords from keyring
+    delete_password_from_keyring(&keyring_account(&id))?;
+    delete_password_from_keyring(&ssh_keyring_account(&id))?;
 
     // Remove from connections file
     let mut file = load_connections_file(&app)?;
@@ -165,7 +185,14 @@ pub async fn set_default_connection(app: AppHandle, id: String) -> Result<(), Ap
 
 #[tauri::command]
 pub async fn get_connection_password(id: String) -> Result<Option<String>, AppError> {
-    get_password_from_keyring(&id)
+    get_password_from_keyring(&keyring_account(&id))
+}
+
+/// Returns the SSH password or private key passphrase, depending on the
+/// tunnel's auth method.
+#[tauri::command]
+pub async fn get_ssh_secret(id: String) -> Result<Option<String>, AppError> {
+    get_password_from_keyring(&ssh_keyring_account(&id))
 }
 
 #[tauri::command]
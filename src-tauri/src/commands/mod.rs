@@ -7,3 +7,4 @@ pub mod query_history;
 pub mod schema;
 pub mod settings;
 pub mod table_data;
+pub mod transaction;
@@ -1,45 +1,402 @@
-use crate::db::row_utils::row_to_values;
-use crate::error::AppError;
-use crate::state::AppState;
-use crate::types::{ColumnMetadata, QueryResult};
-use sqlx::{Column, Row};
+use crate::db::describe::describe_columns;
+use crate::db::row_utils::{row_to_values, ValueFormat};
+use crate::db::script::{changes_schema, command_tag, split_statements};
+use crate::db::{DatabaseConnection, Session};
+use crate::error::{is_query_canceled, AppError};
+use crate::state::{AppState, RunningQuery};
+use crate::types::{
+    QueryResult, QueryStreamEvent, ScriptOptions, StatementOutcome, StatementResult,
+    TransactionStatus,
+};
+use futures_util::TryStreamExt;
+use sqlx::postgres::PgConnection;
+use sqlx::{Acquire, Either, Executor};
+use std::time::Instant;
+use tauri::ipc::Channel;
 use tauri::State;
+use tokio::sync::MappedMutexGuard;
 
 #[tauri::command]
 pub async fn execute_query(
+    connection_id: String,
+    query_id: String,
     query: String,
     state: State<'_, AppState>,
 ) -> Result<QueryResult, AppError> {
-    let connection = state.connection.lock().await;
+    let db = state.connection(&connection_id).await?;
+    let mut session = lock_session(&state, &db, &connection_id, &query_id).await?;
 
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+    let start = Instant::now();
 
-    let start = std::time::Instant::now();
+    let result = run_query(&mut session.conn, &query, start).await;
+    let running = state.finish_query(&query_id).await;
+    session.refresh_status().await;
+    if changes_schema(&query) {
+        state.invalidate_schema(&connection_id).await;
+    }
 
-    let rows = sqlx::query(&query).fetch_all(&db.pool).await?;
+    result.map_err(|e| query_error(e, &query_id, cancel_requested(&running)))
+}
 
-    let execution_time_ms = start.elapsed().as_millis() as u64;
+/// Describes and executes a single statement, collecting all of its rows.
+async fn run_query(
+    conn: &mut PgConnection,
+    query: &str,
+    start: Instant,
+) -> Result<QueryResult, sqlx::Error> {
+    let columns = describe_columns(conn, query).await?;
+    let format = ValueFormat::load_for(&mut *conn, columns.iter().map(|c| c.type_oid)).await?;
+    let mut stream = conn.fetch_many(sqlx::query(query));
+    let mut rows = Vec::new();
+    let mut rows_affected = 0;
 
-    let columns = if rows.is_empty() {
-        Vec::new()
-    } else {
-        rows[0]
-            .columns()
-            .iter()
-            .map(|col| ColumnMetadata {
-                name: col.name().to_string(),
-                data_type: col.type_info().to_string(),
-            })
-            .collect()
-    };
-
-    let row_count = rows.len();
-    let rows = rows.into_iter().map(|row| row_to_values(&row)).collect();
+    while let Some(item) = stream.try_next().await? {
+        match item {
+            Either::Left(done) => rows_affected += done.rows_affected(),
+            Either::Right(row) => rows.push(row_to_values(&row, format)),
+        }
+    }
 
     Ok(QueryResult {
         columns,
+        row_count: rows.len(),
         rows,
-        row_count,
-        execution_time_ms,
+        rows_affected,
+        command_tag: command_tag(query, rows_affected),
+        execution_time_ms: start.elapsed().as_millis() as u64,
+    })
+}
+
+/// Default number of rows per `QueryStreamEvent::Rows` batch
+const DEFAULT_BATCH_SIZE: usize = 500;
+
+/// Executes a query and streams its rows to the frontend in batches.
+///
+/// Rows are read incrementally with `fetch`, so memory stays bounded by the
+/// batch size. If the channel stops accepting events the backend query is
+/// cancelled; `cancel_query` with the same query id also stops the stream.
+#[tauri::command]
+pub async fn execute_query_stream(
+    connection_id: String,
+    query_id: String,
+    query: String,
+    batch_size: Option<usize>,
+    on_event: Channel<QueryStreamEvent>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let db = state.connection(&connection_id).await?;
+    let mut session = lock_session(&state, &db, &connection_id, &query_id).await?;
+    let backend_pid = session.backend_pid;
+    let batch_size = batch_size.unwrap_or(DEFAULT_BATCH_SIZE).max(1);
+
+    let start = Instant::now();
+
+    let result = stream_rows(&mut session.conn, &query, batch_size, &on_event).await;
+    let running = state.finish_query(&query_id).await;
+    if changes_schema(&query) {
+        state.invalidate_schema(&connection_id).await;
+    }
+
+    let result = match result {
+        Ok(StreamOutcome::Completed {
+            row_count,
+            rows_affected,
+        }) => {
+            let _ = on_event.send(QueryStreamEvent::Finished {
+                row_count,
+                rows_affected,
+                command_tag: command_tag(&query, rows_affected),
+                execution_time_ms: start.elapsed().as_millis() as u64,
+            });
+            Ok(())
+        }
+        Ok(StreamOutcome::Dropped) => {
+            // Stop the backend before the session is used again, otherwise
+            // the unread rows would be drained over the network.
+            sqlx::query("SELECT pg_cancel_backend($1)")
+                .bind(backend_pid)
+                .execute(&db.pool)
+                .await
+                .map(|_| ())
+                .map_err(AppError::from)
+        }
+        Err(e) => Err(query_error(e, &query_id, cancel_requested(&running))),
+    };
+    // Whatever happened, the status must match the session again
+    session.refresh_status().await;
+
+    result
+}
+
+enum StreamOutcome {
+    Completed {
+        row_count: usize,
+        rows_affected: u64,
+    },
+    Dropped,
+}
+
+async fn stream_rows(
+    conn: &mut PgConnection,
+    query: &str,
+    batch_size: usize,
+    on_event: &Channel<QueryStreamEvent>,
+) -> Result<StreamOutcome, sqlx::Error> {
+    let send = |event: QueryStreamEvent| on_event.send(event).is_ok();
+
+    let columns = describe_columns(conn, query).await?;
+    let format = ValueFormat::load_for(&mut *conn, columns.iter().map(|c| c.type_oid)).await?;
+    if !send(QueryStreamEvent::Columns { columns }) {
+        return Ok(StreamOutcome::Dropped);
+    }
+
+    let mut stream = conn.fetch_many(sqlx::query(query));
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut rows_sent = 0;
+    let mut rows_affected = 0;
+
+    while let Some(item) = stream.try_next().await? {
+        let row = match item {
+            Either::Left(done) => {
+                rows_affected += done.rows_affected();
+                continue;
+            }
+            Either::Right(row) => row,
+        };
+
+        batch.push(row_to_values(&row, format));
+        if batch.len() >= batch_size {
+            rows_sent += batch.len();
+            let rows = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+            if !send(QueryStreamEvent::Rows { rows, rows_sent }) {
+                return Ok(StreamOutcome::Dropped);
+            }
+        }
+    }
+
+    if !batch.is_empty() {
+        rows_sent += batch.len();
+        if !send(QueryStreamEvent::Rows {
+            rows: batch,
+            rows_sent,
+        }) {
+            return Ok(StreamOutcome::Dropped);
+        }
+    }
+
+    Ok(StreamOutcome::Completed {
+        row_count: rows_sent,
+        rows_affected,
     })
 }
+
+/// Executes a multi-statement script and returns one result per statement.
+///
+/// Statements run in order on the connection's session, so settings carry
+/// over from one statement to the next. `cancel_query` with the same query
+/// id stops the script; the remaining statements are skipped.
+#[tauri::command]
+pub async fn execute_script(
+    connection_id: String,
+    query_id: String,
+    script: String,
+    options: ScriptOptions,
+    state: State<'_, AppState>,
+) -> Result<Vec<StatementResult>, AppError> {
+    let db = state.connection(&connection_id).await?;
+    let mut session = lock_session(&state, &db, &connection_id, &query_id).await?;
+
+    if options.use_transaction && session.status != TransactionStatus::Idle {
+        state.finish_query(&query_id).await;
+        return Err(AppError::Transaction(
+            "Cannot run the script in its own transaction while one is open".to_string(),
+        ));
+    }
+
+    let statements = split_statements(&script);
+    let changes_schema = statements.iter().any(|s| changes_schema(s));
+    let result = run_script(&state, &query_id, &mut session.conn, statements, &options).await;
+    state.finish_query(&query_id).await;
+    session.refresh_status().await;
+    if changes_schema {
+        state.invalidate_schema(&connection_id).await;
+    }
+
+    Ok(result?)
+}
+
+async fn run_script(
+    state: &AppState,
+    query_id: &str,
+    conn: &mut PgConnection,
+    statements: Vec<String>,
+    options: &ScriptOptions,
+) -> Result<Vec<StatementResult>, sqlx::Error> {
+    if !options.use_transaction {
+        let (results, _) = run_statements(state, query_id, conn, statements, options).await?;
+        return Ok(results);
+    }
+
+    let mut tx = conn.begin().await?;
+    let (results, stopped) = run_statements(state, query_id, &mut tx, statements, options).await?;
+    if stopped {
+        tx.rollback().await?;
+    } else {
+        tx.commit().await?;
+    }
+
+    Ok(results)
+}
+
+/// Runs statements in order. Returns the results and whether execution
+/// stopped early because of an error or a cancel.
+async fn run_statements(
+    state: &AppState,
+    query_id: &str,
+    conn: &mut PgConnection,
+    statements: Vec<String>,
+    options: &ScriptOptions,
+) -> Result<(Vec<StatementResult>, bool), sqlx::Error> {
+    // Inside a script transaction a failed statement aborts the whole
+    // transaction, so wrap each statement in a savepoint to continue.
+    let use_savepoints = options.use_transaction && !options.stop_on_error;
+    let mut results = Vec::with_capacity(statements.len());
+    let mut stopped = false;
+
+    for statement in statements {
+        if stopped {
+            results.push(StatementResult {
+                statement,
+                outcome: StatementOutcome::Skipped,
+                execution_time_ms: 0,
+            });
+            continue;
+        }
+
+        let start = Instant::now();
+        let outcome = if use_savepoints {
+            let mut savepoint = conn.begin().await?;
+            let outcome = run_statement(&mut savepoint, &statement, start).await;
+            if outcome.is_ok() {
+                savepoint.commit().await?;
+            } else {
+                savepoint.rollback().await?;
+            }
+            outcome
+        } else {
+            run_statement(conn, &statement, start).await
+        };
+        let execution_time_ms = start.elapsed().as_millis() as u64;
+
+        let outcome = match outcome {
+            Ok(outcome) => outcome,
+            Err(e) => {
+                let cancelled = state.is_cancel_requested(query_id).await;
+                stopped = options.stop_on_error || cancelled;
+                StatementOutcome::Error {
+                    message: query_error(e, query_id, cancelled).to_string(),
+                }
+            }
+        };
+
+        results.push(StatementResult {
+            statement,
+            outcome,
+            execution_time_ms,
+        });
+    }
+
+    Ok((results, stopped))
+}
+
+async fn run_statement(
+    conn: &mut PgConnection,
+    statement: &str,
+    start: Instant,
+) -> Result<StatementOutcome, sqlx::Error> {
+    let result = run_query(conn, statement, start).await?;
+
+    if result.columns.is_empty() {
+        return Ok(StatementOutcome::RowsAffected {
+            rows_affected: result.rows_affected,
+            command_tag: result.command_tag,
+        });
+    }
+
+    Ok(StatementOutcome::ResultSet { result })
+}
+
+/// Cancels a running query via `pg_cancel_backend` on a separate pool
+/// connection. Returns false if the query already finished.
+///
+/// A query still waiting for the session is cancelled by marking it; it
+/// gives up once it gets the session.
+#[tauri::command]
+pub async fn cancel_query(query_id: String, state: State<'_, AppState>) -> Result<bool, AppError> {
+    let connection_id = match state.running_queries.lock().await.get(&query_id) {
+        Some(query) => query.connection_id.clone(),
+        None => return Ok(false),
+    };
+    let db = state.connection(&connection_id).await?;
+    let mut conn = db.pool.acquire().await?;
+
+    // Held while cancelling, so the query can't finish meanwhile and leave
+    // its backend running the next query
+    let mut running = state.running_queries.lock().await;
+    let Some(query) = running.get_mut(&query_id) else {
+        return Ok(false);
+    };
+    query.cancel_requested = true;
+    let Some(backend_pid) = query.backend_pid else {
+        return Ok(true);
+    };
+
+    let cancelled: bool = sqlx::query_scalar("SELECT pg_cancel_backend($1)")
+        .bind(backend_pid)
+        .fetch_one(&mut *conn)
+        .await?;
+
+    Ok(cancelled)
+}
+
+/// A cancelled statement is also how `statement_timeout` ends, so only
+/// treat it as a user cancel when one was requested.
+fn query_error(e: sqlx::Error, query_id: &str, cancel_requested: bool) -> AppError {
+    if cancel_requested && is_query_canceled(&e) {
+        AppError::QueryCancelled(query_id.to_string())
+    } else {
+        e.into()
+    }
+}
+
+fn cancel_requested(running: &Option<RunningQuery>) -> bool {
+    running.as_ref().is_some_and(|q| q.cancel_requested)
+}
+
+/// Locks the connection's session for a query and registers its backend
+/// PID under `query_id` so `cancel_query` can reach it.
+///
+/// The query is registered before waiting for the session, so it can be
+/// cancelled while another query holds it.
+async fn lock_session<'a>(
+    state: &AppState,
+    db: &'a DatabaseConnection,
+    connection_id: &str,
+    query_id: &str,
+) -> Result<MappedMutexGuard<'a, Session>, AppError> {
+    state.register_query(query_id, connection_id).await;
+    let session = match db.session().await {
+        Ok(session) => session,
+        Err(e) => {
+            state.finish_query(query_id).await;
+            return Err(e);
+        }
+    };
+
+    if !state.start_query(query_id, session.backend_pid).await {
+        state.finish_query(query_id).await;
+        return Err(AppError::QueryCancelled(query_id.to_string()));
+    }
+
+    Ok(session)
+}
@@ -1,83 +1,144 @@
 use crate::db::queries;
+use crate::db::row_key::relation_kind;
+use crate::db::schema_cache::cached;
 use crate::error::AppError;
 use crate::state::AppState;
 use crate::types::{
-    ColumnInfo, ConstraintInfo, ForeignKeyInfo, IndexInfo, SchemaInfo, TableDetailInfo, TableInfo,
+    ColumnInfo, ConstraintInfo, ForeignKeyInfo, IndexInfo, RelationKind, SchemaInfo,
+    TableDetailInfo, TableInfo,
 };
-use sqlx::Row;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
 use tauri::State;
 
+/// Get schemas with their tables and columns.
+///
+/// Loads every schema, or only `schema_names` if given, with one query for
+/// the tables and one for the columns of all of them.
 #[tauri::command]
-pub async fn get_schemas(state: State<'_, AppState>) -> Result<Vec<SchemaInfo>, AppError> {
-    let connection = state.connection.lock().await;
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+pub async fn get_schemas(
+    connection_id: String,
+    schema_names: Option<Vec<String>>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SchemaInfo>, AppError> {
+    let db = state.connection(&connection_id).await?;
 
-    let schema_rows = sqlx::query(queries::GET_SCHEMAS)
-        .fetch_all(&db.pool)
-        .await?;
+    let schema_names = match schema_names {
+        Some(names) => names,
+        None => cached_schema_names(&state, &connection_id, &db.pool).await?,
+    };
 
-    let mut schemas = Vec::new();
-
-    for schema_row in schema_rows {
-        let schema_name: String = schema_row.try_get("schema_name")?;
-
-        let table_rows = sqlx::query(queries::GET_TABLES)
-            .bind(&schema_name)
-            .fetch_all(&db.pool)
-            .await?;
-
-        let mut tables = Vec::new();
-
-        for table_row in table_rows {
-            let table_name: String = table_row.try_get("table_name")?;
-
-            let column_rows = sqlx::query(queries::GET_COLUMNS)
-                .bind(&schema_name)
-                .bind(&table_name)
-                .fetch_all(&db.pool)
-                .await?;
-
-            let columns: Vec<ColumnInfo> = column_rows
-                .iter()
-                .map(|row| {
-                    let is_nullable: String = row.try_get("is_nullable").unwrap_or_default();
-                    ColumnInfo {
-                        name: row.try_get("column_name").unwrap_or_default(),
-                        data_type: row.try_get("data_type").unwrap_or_default(),
-                        is_nullable: is_nullable == "YES",
-                        is_primary_key: row.try_get("is_primary_key").unwrap_or(false),
-                        default_value: row.try_get("column_default").ok(),
-                    }
-                })
-                .collect();
-
-            tables.push(TableInfo {
-                schema: schema_name.clone(),
-                name: table_name,
-                columns,
-            });
-        }
-
-        schemas.push(SchemaInfo {
-            name: schema_name,
-            tables,
-        });
-    }
+    cached(
+        &state.schema_caches,
+        &connection_id,
+        &db.pool,
+        |cache| schema_names.iter().map(|name| cache.schema(name)).collect(),
+        load_schemas(&db.pool, &schema_names),
+        |cache, schemas| {
+            for schema in schemas {
+                cache.put_schema(schema);
+            }
+        },
+    )
+    .await
+}
 
-    Ok(schemas)
+/// Get the names of the schemas, without loading their tables
+#[tauri::command]
+pub async fn get_schema_names(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<String>, AppError> {
+    let db = state.connection(&connection_id).await?;
+
+    cached_schema_names(&state, &connection_id, &db.pool).await
+}
+
+/// Get the tables of a schema, without their columns
+#[tauri::command]
+pub async fn get_tables(
+    connection_id: String,
+    schema_name: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<TableInfo>, AppError> {
+    let db = state.connection(&connection_id).await?;
+
+    cached(
+        &state.schema_caches,
+        &connection_id,
+        &db.pool,
+        |cache| cache.tables.get(&schema_name).cloned(),
+        list_tables(&db.pool, std::slice::from_ref(&schema_name)),
+        |cache, tables| {
+            cache.tables.insert(schema_name.clone(), tables);
+        },
+    )
+    .await
+}
+
+/// Get the columns of a table
+#[tauri::command]
+pub async fn get_columns(
+    connection_id: String,
+    schema_name: String,
+    table_name: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ColumnInfo>, AppError> {
+    let db = state.connection(&connection_id).await?;
+
+    let key = (schema_name, table_name);
+    cached(
+        &state.schema_caches,
+        &connection_id,
+        &db.pool,
+        |cache| cache.columns.get(&key).cloned(),
+        list_columns(&db.pool, &key.0, &key.1),
+        |cache, columns| {
+            cache.columns.insert(key.clone(), columns);
+        },
+    )
+    .await
+}
+
+/// Drop cached schema metadata, so it's read again from the catalog
+#[tauri::command]
+pub async fn refresh_schema(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.invalidate_schema(&connection_id).await;
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn get_foreign_keys(
+    connection_id: String,
     schema_name: String,
     state: State<'_, AppState>,
 ) -> Result<Vec<ForeignKeyInfo>, AppError> {
-    let connection = state.connection.lock().await;
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+    let db = state.connection(&connection_id).await?;
 
+    cached(
+        &state.schema_caches,
+        &connection_id,
+        &db.pool,
+        |cache| cache.foreign_keys.get(&schema_name).cloned(),
+        load_foreign_keys(&db.pool, &schema_name),
+        |cache, foreign_keys| {
+            cache.foreign_keys.insert(schema_name.clone(), foreign_keys);
+        },
+    )
+    .await
+}
+
+async fn load_foreign_keys(
+    pool: &PgPool,
+    schema_name: &str,
+) -> Result<Vec<ForeignKeyInfo>, AppError> {
     let rows = sqlx::query(queries::GET_FOREIGN_KEYS)
-        .bind(&schema_name)
-        .fetch_all(&db.pool)
+        .bind(schema_name)
+        .fetch_all(pool)
         .await?;
 
     let foreign_keys: Vec<ForeignKeyInfo> = rows
@@ -98,39 +159,42 @@ pub async fn get_foreign_keys(
 
 #[tauri::command]
 pub async fn get_table_detail(
+    connection_id: String,
     schema_name: String,
     table_name: String,
     state: State<'_, AppState>,
 ) -> Result<TableDetailInfo, AppError> {
-    let connection = state.connection.lock().await;
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+    let db = state.connection(&connection_id).await?;
 
-    // Get columns
-    let column_rows = sqlx::query(queries::GET_COLUMNS)
-        .bind(&schema_name)
-        .bind(&table_name)
-        .fetch_all(&db.pool)
-        .await?;
+    let key = (schema_name, table_name);
+    cached(
+        &state.schema_caches,
+        &connection_id,
+        &db.pool,
+        |cache| cache.table_details.get(&key).cloned(),
+        load_table_detail(&db.pool, key.0.clone(), key.1.clone()),
+        |cache, detail| {
+            cache.table_details.insert(key.clone(), detail);
+        },
+    )
+    .await
+}
 
-    let columns: Vec<ColumnInfo> = column_rows
-        .iter()
-        .map(|row| {
-            let is_nullable: String = row.try_get("is_nullable").unwrap_or_default();
-            ColumnInfo {
-                name: row.try_get("column_name").unwrap_or_default(),
-                data_type: row.try_get("data_type").unwrap_or_default(),
-                is_nullable: is_nullable == "YES",
-                is_primary_key: row.try_get("is_primary_key").unwrap_or(false),
-                default_value: row.try_get("column_default").ok(),
-            }
-        })
-        .collect();
+async fn load_table_detail(
+    pool: &PgPool,
+    schema_name: String,
+    table_name: String,
+) -> Result<TableDetailInfo, AppError> {
+    let kind = relation_kind(pool, &schema_name, &table_name).await?;
+
+    // Get columns
+    let columns = list_columns(pool, &schema_name, &table_name).await?;
 
     // Get indexes
     let index_rows = sqlx::query(queries::GET_INDEXES)
         .bind(&schema_name)
         .bind(&table_name)
-        .fetch_all(&db.pool)
+        .fetch_all(pool)
         .await?;
 
     let indexes: Vec<IndexInfo> = index_rows
@@ -147,7 +211,7 @@ pub async fn get_table_detail(
     let constraint_rows = sqlx::query(queries::GET_CONSTRAINTS)
         .bind(&schema_name)
         .bind(&table_name)
-        .fetch_all(&db.pool)
+        .fetch_all(pool)
         .await?;
 
     let constraints: Vec<ConstraintInfo> = constraint_rows
@@ -169,7 +233,7 @@ pub async fn get_table_detail(
     let fk_rows = sqlx::query(queries::GET_FOREIGN_KEYS_FOR_TABLE)
         .bind(&schema_name)
         .bind(&table_name)
-        .fetch_all(&db.pool)
+        .fetch_all(pool)
         .await?;
 
     let foreign_keys: Vec<ForeignKeyInfo> = fk_rows
@@ -188,9 +252,136 @@ pub async fn get_table_detail(
     Ok(TableDetailInfo {
         schema: schema_name,
         name: table_name,
+        kind,
         columns,
         indexes,
         constraints,
         foreign_keys,
     })
 }
+
+/// Get the `SELECT` defining a view or materialized view
+#[tauri::command]
+pub async fn get_view_definition(
+    connection_id: String,
+    schema_name: String,
+    view_name: String,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let db = state.connection(&connection_id).await?;
+
+    let definition: Option<String> = sqlx::query_scalar(queries::GET_VIEW_DEFINITION)
+        .bind(&schema_name)
+        .bind(&view_name)
+        .fetch_optional(&db.pool)
+        .await?;
+
+    definition.ok_or_else(|| {
+        AppError::InvalidConfig(format!(
+            "\"{}\".\"{}\" is not a view",
+            schema_name, view_name
+        ))
+    })
+}
+
+async fn cached_schema_names(
+    state: &AppState,
+    connection_id: &str,
+    pool: &PgPool,
+) -> Result<Vec<String>, AppError> {
+    cached(
+        &state.schema_caches,
+        connection_id,
+        pool,
+        |cache| cache.schema_names.clone(),
+        list_schema_names(pool),
+        |cache, names| cache.schema_names = Some(names),
+    )
+    .await
+}
+
+async fn list_schema_names(pool: &PgPool) -> Result<Vec<String>, AppError> {
+    let names = sqlx::query_scalar(queries::GET_SCHEMAS)
+        .fetch_all(pool)
+        .await?;
+    Ok(names)
+}
+
+/// Loads schemas with their tables and columns.
+async fn load_schemas(pool: &PgPool, schema_names: &[String]) -> Result<Vec<SchemaInfo>, AppError> {
+    let mut tables = list_tables(pool, schema_names).await?;
+
+    let column_rows = sqlx::query(queries::GET_SCHEMA_COLUMNS)
+        .bind(schema_names)
+        .fetch_all(pool)
+        .await?;
+    let mut columns: HashMap<(String, String), Vec<ColumnInfo>> = HashMap::new();
+    for row in &column_rows {
+        let key = (row.try_get("table_schema")?, row.try_get("table_name")?);
+        columns.entry(key).or_default().push(column_info(row));
+    }
+    for table in &mut tables {
+        let key = (table.schema.clone(), table.name.clone());
+        table.columns = columns.remove(&key).unwrap_or_default();
+    }
+
+    let schemas = schema_names
+        .iter()
+        .map(|name| SchemaInfo {
+            name: name.clone(),
+            tables: tables.extract_if(.., |t| &t.schema == name).collect(),
+        })
+        .collect();
+
+    Ok(schemas)
+}
+
+/// Lists the tables of `schema_names`, with no columns.
+async fn list_tables(pool: &PgPool, schema_names: &[String]) -> Result<Vec<TableInfo>, AppError> {
+    let rows = sqlx::query(queries::GET_TABLES)
+        .bind(schema_names)
+        .fetch_all(pool)
+        .await?;
+
+    let mut tables = Vec::with_capacity(rows.len());
+    for row in &rows {
+        let relkind: String = row.try_get("relkind")?;
+        let Some(kind) = RelationKind::from_relkind(&relkind) else {
+            continue;
+        };
+        tables.push(TableInfo {
+            schema: row.try_get("table_schema")?,
+            name: row.try_get("table_name")?,
+            kind,
+            parent_schema: row.try_get("parent_schema")?,
+            parent_table: row.try_get("parent_table")?,
+            columns: Vec::new(),
+        });
+    }
+
+    Ok(tables)
+}
+
+async fn list_columns(
+    pool: &PgPool,
+    schema_name: &str,
+    table_name: &str,
+) -> Result<Vec<ColumnInfo>, AppError> {
+    let rows = sqlx::query(queries::GET_COLUMNS)
+        .bind(schema_name)
+        .bind(table_name)
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.iter().map(column_info).collect())
+}
+
+fn column_info(row: &PgRow) -> ColumnInfo {
+    let is_nullable: String = row.try_get("is_nullable").unwrap_or_default();
+    ColumnInfo {
+        name: row.try_get("column_name").unwrap_or_default(),
+        data_type: row.try_get("data_type").unwrap_or_default(),
+        is_nullable: is_nullable == "YES",
+        is_primary_key: row.try_get("is_primary_key").unwrap_or(false),
+        default_value: row.try_get("column_default").ok().flatten(),
+    }
+}
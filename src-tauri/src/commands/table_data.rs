@@ -1,266 +1,1150 @@
-use crate::db::row_utils::{get_column_value, row_to_values};
+use crate::db::binding::{cell_error, find_column, locate_cell_error, param_text, Params};
+use crate::db::count::{estimate_count, exact_count};
+use crate::db::cursor::{reverse_sort, seek_clause, Cursor};
+use crate::db::filter::filter_clause;
+use crate::db::queries;
+use crate::db::row_key::{relation_kind, row_key, value_matches, RowKey};
+use crate::db::row_utils::{row_to_values, ValueFormat};
+use crate::db::sort::{effective_sort, order_by_clause};
 use crate::db::sql_utils::{safe_identifier, safe_table_ref};
-use crate::error::AppError;
+use crate::db::undo::{generated_columns, inverse_statement, RowImage, UndoTarget};
+use crate::db::DatabaseConnection;
+use crate::error::{is_query_canceled, AppError};
 use crate::state::AppState;
 use crate::types::{
-    RowDelete, RowInsert, RowUpdate, TableColumnInfo, TableData, TableDataRequest, TableRow,
+    ChangeError, ChangeKind, ChangePreview, ChangeRecord, ChangeSet, ChangeSetResult, RelationKind,
+    RowCount, RowDelete, RowInsert, RowUpdate, StatementPreview, TableColumnInfo, TableData,
+    TableDataRequest, TableFilter, TableRow,
 };
-use sqlx::postgres::PgRow;
-use sqlx::{Column, Row};
-use std::collections::HashMap;
+use sqlx::postgres::{PgConnection, PgRow};
+use sqlx::{Acquire, PgPool, Row};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use tauri::State;
 
+/// Most bind parameters PostgreSQL accepts in one statement
+const MAX_PARAMS: usize = u16::MAX as usize;
+
 /// Get table data with pagination
 #[tauri::command]
 pub async fn get_table_data(
+    connection_id: String,
     request: TableDataRequest,
     state: State<'_, AppState>,
 ) -> Result<TableData, AppError> {
-    let connection = state.connection.lock().await;
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+    let db = state.connection(&connection_id).await?;
 
-    // Get column info with primary key and auto-generated info
-    let columns = get_column_info(&db.pool, &request.schema, &request.table).await?;
+    // Get column info with primary key and auto-generated info, and decide
+    // how rows are identified
+    let target = EditTarget::load(&db.pool, &request.schema, &request.table).await?;
+    let columns = &target.columns;
     let primary_keys: Vec<String> = columns
         .iter()
         .filter(|c| c.is_primary_key)
         .map(|c| c.name.clone())
         .collect();
     let has_primary_key = !primary_keys.is_empty();
+    let table_ref = &target.table_ref;
+
+    // Compile the filter; its values are bound as parameters
+    let mut params = Params::default();
+    let mut conditions = Vec::new();
+    if let Some(filter) = &request.filter {
+        conditions.push(filter_clause(filter, columns, &mut params)?);
+    }
+
+    // Estimate the count of matching rows; an exact count is asked for
+    // separately with `count_table_rows`
+    let total_count =
+        estimate_count(&db.pool, table_ref, &where_clause(&conditions), &params).await?;
 
-    // Validate identifiers to prevent SQL injection
-    let table_ref = safe_table_ref(&request.schema, &request.table)?;
+    // Sort in a total order so pages don't overlap. With a unique key to
+    // break ties, pages can be found by seeking from a cursor; otherwise
+    // only OFFSET is possible.
+    let seek_key = target.key.as_ref().filter(|k| k.is_sortable());
+    let sort = effective_sort(&request.sort, seek_key.map_or(&[], |k| &k.columns));
+    let cursor = match &request.cursor {
+        Some(_) if seek_key.is_none() => {
+            return Err(AppError::InvalidCursor(
+                "table has no unique key to seek on".to_string(),
+            ))
+        }
+        Some(encoded) => Some(Cursor::decode(encoded, &sort)?),
+        None => None,
+    };
+    let backwards = cursor.as_ref().is_some_and(|c| c.before);
+    let query_sort = if backwards {
+        reverse_sort(&sort)
+    } else {
+        sort.clone()
+    };
 
-    // Get total count
-    let count_query = format!("SELECT COUNT(*) as count FROM {}", table_ref);
-    let count_row = sqlx::query(&count_query).fetch_one(&db.pool).await?;
-    let total_count: i64 = count_row.try_get("count")?;
+    let mut pagination = match &cursor {
+        Some(cursor) => {
+            conditions.push(seek_clause(
+                &query_sort,
+                &cursor.values,
+                columns,
+                &mut params,
+            )?);
+            String::new()
+        }
+        None => format!(" OFFSET {}", request.offset),
+    };
+    // Fetch one extra row to learn whether another page follows
+    let limit = if seek_key.is_some() {
+        request.limit + 1
+    } else {
+        request.limit
+    };
+    pagination = format!(" LIMIT {}{}", limit, pagination);
 
-    // Get data with pagination
     let data_query = format!(
-        "SELECT * FROM {} LIMIT {} OFFSET {}",
-        table_ref, request.limit, request.offset
+        "SELECT {} FROM {}{}{}{}",
+        target.select_list(),
+        table_ref,
+        where_clause(&conditions),
+        order_by_clause(&query_sort, columns)?,
+        pagination
     );
-    let rows = sqlx::query(&data_query).fetch_all(&db.pool).await?;
+    let mut rows = params
+        .bind(sqlx::query(&data_query))
+        .fetch_all(&db.pool)
+        .await?;
+
+    let has_more = rows.len() > request.limit;
+    rows.truncate(request.limit);
+    if backwards {
+        rows.reverse();
+    }
+
+    let (mut next_cursor, mut prev_cursor) = (None, None);
+    if seek_key.is_some() {
+        let (has_next, has_prev) = match &cursor {
+            Some(cursor) if cursor.before => (true, has_more),
+            Some(_) => (has_more, true),
+            None => (has_more, request.offset > 0),
+        };
+        if has_next {
+            next_cursor = rows
+                .last()
+                .map(|r| Cursor::from_row(r, &sort, false, target.format).encode());
+        }
+        if has_prev {
+            prev_cursor = rows
+                .first()
+                .map(|r| Cursor::from_row(r, &sort, true, target.format).encode());
+        }
+    }
 
     // Convert rows
-    let table_rows: Vec<TableRow> = rows
-        .iter()
-        .map(|row| {
-            let id = if has_primary_key {
-                build_row_id(row, &primary_keys)
-            } else {
-                String::new()
-            };
-            let values = row_to_values(row);
-            TableRow { id, values }
-        })
-        .collect();
+    let table_rows: Vec<TableRow> = rows.iter().map(|row| target.table_row(row)).collect();
+    let row_key = target.key.as_ref().map(|k| k.kind);
 
     Ok(TableData {
-        columns,
+        columns: target.columns,
         rows: table_rows,
-        total_count: total_count as usize,
+        total_count,
+        is_count_estimated: true,
         primary_keys,
         has_primary_key,
+        kind: target.kind,
+        row_key,
+        next_cursor,
+        prev_cursor,
     })
 }
 
-/// Get row count for a table
+/// Get the estimated row count of a table
 #[tauri::command]
 pub async fn get_table_row_count(
+    connection_id: String,
     schema: String,
     table: String,
     state: State<'_, AppState>,
-) -> Result<usize, AppError> {
-    let connection = state.connection.lock().await;
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+) -> Result<RowCount, AppError> {
+    let db = state.connection(&connection_id).await?;
 
     let table_ref = safe_table_ref(&schema, &table)?;
-    let count_query = format!("SELECT COUNT(*) as count FROM {}", table_ref);
-    let count_row = sqlx::query(&count_query).fetch_one(&db.pool).await?;
-    let count: i64 = count_row.try_get("count")?;
+    let count = estimate_count(&db.pool, &table_ref, "", &Params::default()).await?;
 
-    Ok(count as usize)
+    Ok(RowCount {
+        count,
+        is_estimated: true,
+    })
 }
 
-/// Insert rows into table
+/// Default time allowed for an exact count
+const DEFAULT_COUNT_TIMEOUT_MS: u64 = 60_000;
+
+/// Count the rows of a table matching an optional filter exactly.
+///
+/// The count runs under `query_id`, so `cancel_query` can stop it, and is
+/// stopped after `timeout_ms` (a minute by default).
 #[tauri::command]
-pub async fn insert_rows(
+pub async fn count_table_rows(
+    connection_id: String,
+    query_id: String,
     schema: String,
     table: String,
-    rows: Vec<RowInsert>,
+    filter: Option<TableFilter>,
+    timeout_ms: Option<u64>,
     state: State<'_, AppState>,
 ) -> Result<usize, AppError> {
-    let connection = state.connection.lock().await;
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+    let db = state.connection(&connection_id).await?;
 
     let table_ref = safe_table_ref(&schema, &table)?;
-    let mut inserted = 0;
+    let columns = get_column_info(&db.pool, &schema, &table).await?;
+    let mut params = Params::default();
+    let mut conditions = Vec::new();
+    if let Some(filter) = &filter {
+        conditions.push(filter_clause(filter, &columns, &mut params)?);
+    }
+    let timeout_ms = timeout_ms.unwrap_or(DEFAULT_COUNT_TIMEOUT_MS);
 
-    for row in rows {
-        if row.values.is_empty() {
-            continue;
+    // Registered first, so the count can be cancelled while it waits for a
+    // pool connection
+    state.register_query(&query_id, &connection_id).await;
+    let backend = async {
+        let mut conn = db.pool.acquire().await?;
+        let backend_pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+            .fetch_one(&mut *conn)
+            .await?;
+        Ok::<_, sqlx::Error>((conn, backend_pid))
+    }
+    .await;
+    let mut conn = match backend {
+        Ok((conn, backend_pid)) if state.start_query(&query_id, backend_pid).await => conn,
+        Ok(_) => {
+            state.finish_query(&query_id).await;
+            return Err(AppError::QueryCancelled(query_id));
         }
+        Err(e) => {
+            state.finish_query(&query_id).await;
+            return Err(e.into());
+        }
+    };
 
-        let columns: Vec<&String> = row.values.keys().collect();
-        let column_names = columns
-            .iter()
-            .map(|c| safe_identifier(c))
-            .collect::<Result<Vec<_>, _>>()?
-            .join(", ");
-        let placeholders = (1..=columns.len())
-            .map(|i| format!("${}", i))
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        let query = format!(
-            "INSERT INTO {} ({}) VALUES ({})",
-            table_ref, column_names, placeholders
-        );
+    let where_sql = where_clause(&conditions);
+    let result = exact_count(&mut conn, &table_ref, &where_sql, &params, timeout_ms).await;
+    let running = state.finish_query(&query_id).await;
 
-        let mut q = sqlx::query(&query);
-        for col in &columns {
-            let value = &row.values[*col];
-            q = bind_json_value(q, value);
+    match result {
+        Ok(count) => Ok(count as usize),
+        Err(e) if is_query_canceled(&e) => {
+            if running.is_some_and(|q| q.cancel_requested) {
+                Err(AppError::QueryCancelled(query_id))
+            } else {
+                Err(AppError::Timeout(format!(
+                    "counting rows of {} took longer than {} ms",
+                    table_ref, timeout_ms
+                )))
+            }
         }
-
-        q.execute(&db.pool).await?;
-        inserted += 1;
+        Err(e) => Err(e.into()),
     }
+}
+
+/// Insert rows into table, returning them as stored
+///
+/// Rows are inserted with as few multi-row statements as possible, in one
+/// transaction. Returned rows include generated values such as serial ids,
+/// defaults and changes made by triggers.
+#[tauri::command]
+pub async fn insert_rows(
+    connection_id: String,
+    schema: String,
+    table: String,
+    rows: Vec<RowInsert>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TableRow>, AppError> {
+    let db = state.connection(&connection_id).await?;
+
+    let target = EditTarget::load(&db.pool, &schema, &table).await?;
+    let rows: Vec<&RowInsert> = rows.iter().collect();
+
+    let mut tx = db.pool.begin().await?;
+    let inserted = insert_batch(&mut tx, &db.pool, &target, &rows).await?;
+    tx.commit().await?;
+
+    let images = inserted.iter().map(|row| RowImage {
+        before: None,
+        after: Some(row.clone()),
+    });
+    target.record(&db, &schema, &table, images.collect()).await;
 
     Ok(inserted)
 }
 
 /// Update rows in table
+///
+/// Stops at the first row that is gone or was changed since it was loaded;
+/// the rows before it stay updated.
 #[tauri::command]
 pub async fn update_rows(
+    connection_id: String,
     schema: String,
     table: String,
     updates: Vec<RowUpdate>,
     state: State<'_, AppState>,
 ) -> Result<usize, AppError> {
-    let connection = state.connection.lock().await;
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+    let db = state.connection(&connection_id).await?;
 
-    let table_ref = safe_table_ref(&schema, &table)?;
+    let target = EditTarget::load(&db.pool, &schema, &table).await?;
+    let mut conn = db.pool.acquire().await?;
+    let mut images = Vec::new();
+    let result = update_each(&mut conn, &target, &updates, &mut images).await;
 
-    // Get primary keys for the table
-    let columns = get_column_info(&db.pool, &schema, &table).await?;
-    let primary_keys: Vec<String> = columns
-        .iter()
-        .filter(|c| c.is_primary_key)
-        .map(|c| c.name.clone())
-        .collect();
+    target.record(&db, &schema, &table, images).await;
+    result.map(|()| updates.len())
+}
 
-    if primary_keys.is_empty() {
-        return Err(AppError::InvalidConfig(
-            "Cannot update table without primary key".to_string(),
-        ));
-    }
+/// Delete rows from table
+///
+/// Stops at the first row that is gone or was changed since it was loaded;
+/// the rows before it stay deleted.
+#[tauri::command]
+pub async fn delete_rows(
+    connection_id: String,
+    schema: String,
+    table: String,
+    deletes: Vec<RowDelete>,
+    state: State<'_, AppState>,
+) -> Result<usize, AppError> {
+    let db = state.connection(&connection_id).await?;
+
+    let target = EditTarget::load(&db.pool, &schema, &table).await?;
+    let mut conn = db.pool.acquire().await?;
+    let mut images = Vec::new();
+    let result = delete_each(&mut conn, &target, &deletes, &mut images).await;
+
+    let deleted = images.len();
+    target.record(&db, &schema, &table, images).await;
+    result.map(|()| deleted)
+}
 
-    let mut updated = 0;
+/// Updates rows for [`update_rows`], pushing the image of each row written.
+///
+/// Each row is updated in its own transaction, holding its lock from reading
+/// the before-image to writing. A row edited more than once is addressed by
+/// its id after the earlier edit and keeps one image, like in
+/// [`apply_changes`].
+async fn update_each(
+    conn: &mut PgConnection,
+    target: &EditTarget,
+    updates: &[RowUpdate],
+    images: &mut Vec<RowImage>,
+) -> Result<(), AppError> {
+    let mut written: HashMap<&str, usize> = HashMap::new();
+    let mut rows = WrittenRows::default();
 
-    for update in updates {
-        let pk_values: HashMap<String, serde_json::Value> =
-            serde_json::from_str(&update.row_id).unwrap_or_default();
+    for (index, update) in updates.iter().enumerate() {
+        let edit = rows.update(update);
 
-        if pk_values.is_empty() {
+        let mut tx = conn.begin().await?;
+        match update_row(&mut tx, target, &edit).await? {
+            Some(image) => {
+                tx.commit().await?;
+                if let Some(row) = &image.after {
+                    rows.record(&update.row_id, row.id.clone());
+                }
+                match written.get(update.row_id.as_str()) {
+                    Some(&i) => images[i].after = image.after,
+                    None => {
+                        written.insert(&update.row_id, images.len());
+                        images.push(image);
+                    }
+                }
+            }
+            None => {
+                let error = unmatched_row(
+                    &mut tx,
+                    target,
+                    ChangeKind::Update,
+                    index,
+                    &update.row_id,
+                    &edit.row_id,
+                )
+                .await?;
+                return Err(conflict(error));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Deletes rows for [`delete_rows`], pushing the image of each row deleted.
+///
+/// A row listed more than once is deleted once.
+async fn delete_each(
+    conn: &mut PgConnection,
+    target: &EditTarget,
+    deletes: &[RowDelete],
+    images: &mut Vec<RowImage>,
+) -> Result<(), AppError> {
+    let mut deleted = HashSet::new();
+
+    for (index, delete) in deletes.iter().enumerate() {
+        if !deleted.insert(delete.row_id.as_str()) {
             continue;
         }
+        match delete_row(conn, target, delete).await? {
+            Some(row) => images.push(RowImage {
+                before: Some(row),
+                after: None,
+            }),
+            None => {
+                let error = unmatched_row(
+                    conn,
+                    target,
+                    ChangeKind::Delete,
+                    index,
+                    &delete.row_id,
+                    &delete.row_id,
+                )
+                .await?;
+                return Err(conflict(error));
+            }
+        }
+    }
+    Ok(())
+}
 
-        // Validate column name
-        let safe_column = safe_identifier(&update.column)?;
+/// Apply inserts, updates and deletes to a table in one transaction.
+///
+/// Each edit runs in its own savepoint, so a failing edit doesn't hide the
+/// errors of the ones after it. If any edit fails, the whole transaction is
+/// rolled back and every failure is reported.
+#[tauri::command]
+pub async fn apply_changes(
+    connection_id: String,
+    changes: ChangeSet,
+    state: State<'_, AppState>,
+) -> Result<ChangeSetResult, AppError> {
+    let db = state.connection(&connection_id).await?;
 
-        // Build WHERE clause for primary key (pk names come from DB, already safe)
-        let where_parts: Vec<String> = primary_keys
-            .iter()
-            .enumerate()
-            .map(|(i, pk)| format!("\"{}\" = ${}", pk, i + 2))
-            .collect();
-        let where_clause = where_parts.join(" AND ");
+    let target = EditTarget::load(&db.pool, &changes.schema, &changes.table).await?;
 
-        let query = format!(
-            "UPDATE {} SET {} = $1 WHERE {}",
-            table_ref, safe_column, where_clause
-        );
+    let mut tx = db.pool.begin().await?;
+    let mut result = ChangeSetResult {
+        committed: false,
+        inserted: 0,
+        updated: 0,
+        deleted: 0,
+        inserted_rows: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    // Each row written keeps one image, from before its first edit to after
+    // its last
+    let mut images: Vec<RowImage> = Vec::new();
+    let mut written: HashMap<&str, usize> = HashMap::new();
+    let mut rows = WrittenRows::default();
+
+    // Same order as the grid has always saved in
+    for (index, update) in changes.updates.iter().enumerate() {
+        let edit = rows.update(update);
+
+        let mut savepoint = tx.begin().await?;
+        match update_row(&mut savepoint, &target, &edit).await {
+            Ok(Some(image)) => {
+                savepoint.commit().await?;
+                result.updated += 1;
+                if let Some(row) = &image.after {
+                    rows.record(&update.row_id, row.id.clone());
+                }
+                match written.get(update.row_id.as_str()) {
+                    Some(&i) => images[i].after = image.after,
+                    None => {
+                        written.insert(&update.row_id, images.len());
+                        images.push(image);
+                    }
+                }
+            }
+            Ok(None) => {
+                savepoint.rollback().await?;
+                let error = unmatched_row(
+                    &mut tx,
+                    &target,
+                    ChangeKind::Update,
+                    index,
+                    &update.row_id,
+                    &edit.row_id,
+                )
+                .await?;
+                result.errors.push(error);
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                result.errors.push(change_error(
+                    ChangeKind::Update,
+                    index,
+                    Some(&update.row_id),
+                    e,
+                ));
+            }
+        }
+    }
+
+    // Insert in one batch; if that fails, insert row by row to find every
+    // failing row
+    let inserts: Vec<&RowInsert> = changes.inserts.iter().collect();
+    let mut savepoint = tx.begin().await?;
+    match insert_batch(&mut savepoint, &db.pool, &target, &inserts).await {
+        Ok(rows) => {
+            savepoint.commit().await?;
+            result.inserted_rows = rows;
+        }
+        Err(_) => {
+            savepoint.rollback().await?;
+            for (index, row) in inserts.into_iter().enumerate() {
+                let mut savepoint = tx.begin().await?;
+                match insert_batch(&mut savepoint, &db.pool, &target, &[row]).await {
+                    Ok(rows) => {
+                        savepoint.commit().await?;
+                        result.inserted_rows.extend(rows);
+                    }
+                    Err(e) => {
+                        savepoint.rollback().await?;
+                        result
+                            .errors
+                            .push(change_error(ChangeKind::Insert, index, None, e));
+                    }
+                }
+            }
+        }
+    }
+    result.inserted = result.inserted_rows.len();
+    images.extend(result.inserted_rows.iter().map(|row| RowImage {
+        before: None,
+        after: Some(row.clone()),
+    }));
 
-        let mut q = sqlx::query(&query);
-        q = bind_json_value(q, &update.new_value);
+    for (index, delete) in changes.deletes.iter().enumerate() {
+        let edit = rows.delete(delete);
 
-        for pk in &primary_keys {
-            if let Some(pk_val) = pk_values.get(pk) {
-                q = bind_json_value(q, pk_val);
+        let mut savepoint = tx.begin().await?;
+        match delete_row(&mut savepoint, &target, &edit).await {
+            Ok(None) => {
+                savepoint.rollback().await?;
+                let error = unmatched_row(
+                    &mut tx,
+                    &target,
+                    ChangeKind::Delete,
+                    index,
+                    &delete.row_id,
+                    &edit.row_id,
+                )
+                .await?;
+                result.errors.push(error);
+            }
+            Ok(Some(row)) => {
+                savepoint.commit().await?;
+                result.deleted += 1;
+                match written.get(delete.row_id.as_str()) {
+                    Some(&i) => images[i].after = None,
+                    None => images.push(RowImage {
+                        before: Some(row),
+                        after: None,
+                    }),
+                }
+            }
+            Err(e) => {
+                savepoint.rollback().await?;
+                result.errors.push(change_error(
+                    ChangeKind::Delete,
+                    index,
+                    Some(&delete.row_id),
+                    e,
+                ));
             }
         }
+    }
 
-        let result = q.execute(&db.pool).await?;
-        updated += result.rows_affected() as usize;
+    if result.errors.is_empty() {
+        tx.commit().await?;
+        result.committed = true;
+        target
+            .record(&db, &changes.schema, &changes.table, images)
+            .await;
+    } else {
+        tx.rollback().await?;
+        result.inserted = 0;
+        result.updated = 0;
+        result.deleted = 0;
+        result.inserted_rows.clear();
     }
 
-    Ok(updated)
+    Ok(result)
 }
 
-/// Delete rows from table
+/// Show the statements [`apply_changes`] would run for a change set,
+/// without running them.
+///
+/// Statements are built as when applying, including the checks that rows
+/// are unchanged since they were loaded. Later edits of a row updated
+/// earlier in the set address it by its new id and skip the check. Rows
+/// identified by `ctid` move when updated, to a location only known once
+/// applied, so their later edits show the `ctid` they were loaded with.
 #[tauri::command]
-pub async fn delete_rows(
-    schema: String,
-    table: String,
-    deletes: Vec<RowDelete>,
+pub async fn preview_changes(
+    connection_id: String,
+    changes: ChangeSet,
     state: State<'_, AppState>,
-) -> Result<usize, AppError> {
-    let connection = state.connection.lock().await;
-    let db = connection.as_ref().ok_or(AppError::NotConnected)?;
+) -> Result<ChangePreview, AppError> {
+    let db = state.connection(&connection_id).await?;
 
-    let table_ref = safe_table_ref(&schema, &table)?;
+    let target = EditTarget::load(&db.pool, &changes.schema, &changes.table).await?;
+    change_preview(&target, &changes)
+}
 
-    // Get primary keys for the table
-    let columns = get_column_info(&db.pool, &schema, &table).await?;
-    let primary_keys: Vec<String> = columns
+fn change_preview(target: &EditTarget, changes: &ChangeSet) -> Result<ChangePreview, AppError> {
+    let mut statements = Vec::new();
+    let mut rows = WrittenRows::default();
+
+    for (index, update) in changes.updates.iter().enumerate() {
+        let edit = rows.update(update);
+        statements.push(statement_preview(
+            ChangeKind::Update,
+            vec![index],
+            |params| update_statement(target, &edit, params),
+        )?);
+        let row_id =
+            target
+                .key("update")?
+                .updated_id(&edit.row_id, &edit.column, &edit.new_value)?;
+        rows.record(&update.row_id, row_id);
+    }
+
+    let inserts: Vec<&RowInsert> = changes.inserts.iter().collect();
+    let parameterized = insert_statements(target, &inserts, Params::default)?;
+    let inlined = insert_statements(target, &inserts, Params::inline)?;
+    for (statement, inlined) in parameterized.into_iter().zip(inlined) {
+        statements.push(StatementPreview {
+            kind: ChangeKind::Insert,
+            indexes: statement.rows,
+            sql: inlined.sql,
+            parameterized_sql: statement.sql,
+            params: statement.params.values().to_vec(),
+        });
+    }
+
+    for (index, delete) in changes.deletes.iter().enumerate() {
+        let edit = rows.delete(delete);
+        statements.push(statement_preview(
+            ChangeKind::Delete,
+            vec![index],
+            |params| delete_statement(target, &edit, params),
+        )?);
+    }
+
+    let script = statements
         .iter()
-        .filter(|c| c.is_primary_key)
-        .map(|c| c.name.clone())
+        .map(|statement| format!("{};\n", statement.sql))
         .collect();
+    Ok(ChangePreview { statements, script })
+}
 
-    if primary_keys.is_empty() {
-        return Err(AppError::InvalidConfig(
-            "Cannot delete from table without primary key".to_string(),
-        ));
-    }
+/// Revert the last data edit applied on a connection.
+///
+/// The inverse statements run in one transaction. If any row was changed
+/// since the edit, nothing is reverted and the edit stays in the log.
+#[tauri::command]
+pub async fn undo_last_change(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<ChangeRecord, AppError> {
+    let db = state.connection(&connection_id).await?;
 
-    let mut deleted = 0;
+    // Held throughout, so the same edit can't be undone twice
+    let mut log = db.undo_log.lock().await;
+    let entry = log.last().ok_or(AppError::NothingToUndo)?;
 
-    for delete in deletes {
-        let pk_values: HashMap<String, serde_json::Value> =
-            serde_json::from_str(&delete.row_id).unwrap_or_default();
+    let target = EditTarget::load(&db.pool, &entry.schema, &entry.table).await?;
+    let generated = generated_columns(&db.pool, &entry.schema, &entry.table).await?;
+    let undo_target = UndoTarget {
+        table_ref: &target.table_ref,
+        columns: &target.columns,
+        key: target.key("undo changes to")?,
+        generated: &generated,
+    };
 
-        if pk_values.is_empty() {
+    let mut tx = db.pool.begin().await?;
+    let mut rewritten = Vec::new();
+    for image in entry.rows.iter().rev() {
+        let mut params = Params::default();
+        let Some(query) = inverse_statement(entry, image, &undo_target, &mut params)? else {
             continue;
+        };
+
+        let row = params
+            .bind(sqlx::query(&query))
+            .fetch_optional(&mut *tx)
+            .await?;
+        let Some(row) = row else {
+            let row_id = image.after.as_ref().map_or("", |row| &row.id);
+            return Err(AppError::Conflict(format!(
+                "row {} was changed since the edit was applied",
+                row_id
+            )));
+        };
+        if let Some(before) = &image.before {
+            rewritten.push((before.clone(), target.table_row(&row)));
+        }
+    }
+    tx.commit().await?;
+
+    let record = entry.record();
+    log.pop();
+    for (old, new) in &rewritten {
+        log.replace_row(old, new);
+    }
+
+    Ok(record)
+}
+
+/// List the data edits on a connection that can be undone, newest first.
+#[tauri::command]
+pub async fn list_changes(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<ChangeRecord>, AppError> {
+    let db = state.connection(&connection_id).await?;
+    let records = db.undo_log.lock().await.records();
+    Ok(records)
+}
+
+fn change_error(kind: ChangeKind, index: usize, row_id: Option<&str>, e: AppError) -> ChangeError {
+    let (column, message) = match e {
+        AppError::InvalidValue { column, message } => (Some(column), message),
+        e => (None, e.to_string()),
+    };
+    ChangeError {
+        kind,
+        index,
+        row_id: row_id.map(str::to_string),
+        column,
+        message,
+        current_row: None,
+    }
+}
+
+/// Explains why an update or delete matched no row: either the row is gone,
+/// or it was changed since it was loaded, in which case it's returned as it
+/// is now. `current_id` is the id the row was last written under.
+async fn unmatched_row(
+    conn: &mut PgConnection,
+    target: &EditTarget,
+    kind: ChangeKind,
+    index: usize,
+    row_id: &str,
+    current_id: &str,
+) -> Result<ChangeError, AppError> {
+    let current_row = current_row(conn, target, current_id).await?;
+    let message = if current_row.is_some() {
+        "Row was changed by someone else since it was loaded"
+    } else {
+        "Row not found; it may have been deleted"
+    };
+
+    Ok(ChangeError {
+        kind,
+        index,
+        row_id: Some(row_id.to_string()),
+        column: None,
+        message: message.to_string(),
+        current_row,
+    })
+}
+
+/// The rows of a change set written by its earlier edits.
+///
+/// A written row has a new version, and may have a new id if a key column
+/// or, for ctid keys, any value changed. Later edits address the row by
+/// its new id and skip the conflict check, which the version they were
+/// loaded with would fail.
+#[derive(Default)]
+struct WrittenRows<'a> {
+    /// The id each row is written under, by the id it was loaded with
+    ids: HashMap<&'a str, String>,
+}
+
+impl<'a> WrittenRows<'a> {
+    fn record(&mut self, row_id: &'a str, written_id: String) {
+        self.ids.insert(row_id, written_id);
+    }
+
+    fn update<'u>(&self, update: &'u RowUpdate) -> Cow<'u, RowUpdate> {
+        match self.ids.get(update.row_id.as_str()) {
+            Some(row_id) => Cow::Owned(RowUpdate {
+                row_id: row_id.clone(),
+                xmin: None,
+                original_values: None,
+                ..update.clone()
+            }),
+            None => Cow::Borrowed(update),
         }
+    }
 
-        // Build WHERE clause for primary key (pk names come from DB, already safe)
-        let where_parts: Vec<String> = primary_keys
-            .iter()
-            .enumerate()
-            .map(|(i, pk)| format!("\"{}\" = ${}", pk, i + 1))
-            .collect();
-        let where_clause = where_parts.join(" AND ");
+    fn delete<'d>(&self, delete: &'d RowDelete) -> Cow<'d, RowDelete> {
+        match self.ids.get(delete.row_id.as_str()) {
+            Some(row_id) => Cow::Owned(RowDelete {
+                row_id: row_id.clone(),
+                xmin: None,
+                original_values: None,
+            }),
+            None => Cow::Borrowed(delete),
+        }
+    }
+}
 
-        let query = format!("DELETE FROM {} WHERE {}", table_ref, where_clause);
+/// An unmatched row as an error, for commands that stop at the first one
+fn conflict(error: ChangeError) -> AppError {
+    AppError::Conflict(format!(
+        "row {}: {}",
+        error.row_id.unwrap_or_default(),
+        error.message
+    ))
+}
 
-        let mut q = sqlx::query(&query);
+/// A table being edited: its quoted name, kind, columns and how rows are
+/// identified
+struct EditTarget {
+    table_ref: String,
+    kind: RelationKind,
+    columns: Vec<TableColumnInfo>,
+    key: Option<RowKey>,
+    format: ValueFormat,
+}
 
-        for pk in &primary_keys {
-            if let Some(pk_val) = pk_values.get(pk) {
-                q = bind_json_value(q, pk_val);
+impl EditTarget {
+    async fn load(pool: &PgPool, schema: &str, table: &str) -> Result<Self, AppError> {
+        // Validate identifiers to prevent SQL injection
+        let table_ref = safe_table_ref(schema, table)?;
+        let kind = relation_kind(pool, schema, table).await?;
+        let columns = get_column_info(pool, schema, table).await?;
+        let key = row_key(pool, schema, table, kind, &columns).await?;
+        let format = ValueFormat::load(pool).await?;
+
+        Ok(Self {
+            table_ref,
+            kind,
+            columns,
+            key,
+            format,
+        })
+    }
+
+    /// The row key, which existing rows must have to be addressed.
+    fn key(&self, action: &str) -> Result<&RowKey, AppError> {
+        if !self.kind.is_writable() {
+            return Err(AppError::NotEditable(format!(
+                "Cannot {} {}: only tables can be edited",
+                action, self.table_ref
+            )));
+        }
+        self.key.as_ref().ok_or_else(|| {
+            AppError::NotEditable(format!(
+                "Cannot {} {}: its rows have no primary key, unique index or ctid",
+                action, self.table_ref
+            ))
+        })
+    }
+
+    /// Records rows written by an edit in the connection's undo log.
+    async fn record(
+        &self,
+        db: &DatabaseConnection,
+        schema: &str,
+        table: &str,
+        rows: Vec<RowImage>,
+    ) {
+        let columns = self.columns.iter().map(|c| c.name.clone()).collect();
+        db.undo_log.lock().await.push(schema, table, columns, rows);
+    }
+
+    fn select_list(&self) -> &'static str {
+        self.key.as_ref().map_or("*", RowKey::select_list)
+    }
+
+    /// Converts a row selected with [`select_list`](Self::select_list),
+    /// keeping only the table's own columns in `values`.
+    fn table_row(&self, row: &PgRow) -> TableRow {
+        let id = self
+            .key
+            .as_ref()
+            .map(|key| key.row_id(row, self.format))
+            .unwrap_or_default();
+        let mut values = row_to_values(row, self.format);
+        values.truncate(self.columns.len());
+        let xmin = row.try_get::<Option<String>, _>("xmin").ok().flatten();
+
+        TableRow { id, values, xmin }
+    }
+}
+
+/// An `INSERT` of one chunk of rows
+struct InsertStatement<'a> {
+    sql: String,
+    params: Params,
+    /// Values given, with their columns, to find the one at fault
+    cells: Vec<(&'a TableColumnInfo, Option<String>)>,
+    /// Indexes of the rows inserted, among those given
+    rows: Vec<usize>,
+}
+
+/// Builds multi-row `INSERT`s returning the rows as stored.
+///
+/// Every statement lists the columns given in any row, and rows that omit
+/// one use its `DEFAULT`. Rows without values are skipped.
+fn insert_statements<'a>(
+    target: &'a EditTarget,
+    rows: &[&RowInsert],
+    new_params: fn() -> Params,
+) -> Result<Vec<InsertStatement<'a>>, AppError> {
+    target.key("insert into")?;
+    let rows: Vec<(usize, &RowInsert)> = rows
+        .iter()
+        .copied()
+        .enumerate()
+        .filter(|(_, row)| !row.values.is_empty())
+        .collect();
+    for name in rows.iter().flat_map(|(_, row)| row.values.keys()) {
+        find_column(&target.columns, name)?;
+    }
+
+    let columns: Vec<&TableColumnInfo> = target
+        .columns
+        .iter()
+        .filter(|c| rows.iter().any(|(_, row)| row.values.contains_key(&c.name)))
+        .collect();
+    if columns.is_empty() {
+        return Ok(Vec::new());
+    }
+    let column_names = columns
+        .iter()
+        .map(|c| safe_identifier(&c.name))
+        .collect::<Result<Vec<_>, _>>()?
+        .join(", ");
+
+    let mut statements = Vec::new();
+    for chunk in rows.chunks((MAX_PARAMS / columns.len()).max(1)) {
+        let mut params = new_params();
+        let mut cells = Vec::new();
+        let mut tuples = Vec::with_capacity(chunk.len());
+
+        for (_, row) in chunk {
+            let mut values = Vec::with_capacity(columns.len());
+            for column in &columns {
+                match row.values.get(&column.name) {
+                    Some(value) => {
+                        let text = param_text(column, value)?;
+                        values.push(params.push(column, text.clone()));
+                        cells.push((*column, text));
+                    }
+                    None => values.push("DEFAULT".to_string()),
+                }
             }
+            tuples.push(format!("({})", values.join(", ")));
         }
 
-        let result = q.execute(&db.pool).await?;
-        deleted += result.rows_affected() as usize;
+        // RETURNING yields rows in VALUES order for a plain INSERT
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES {} RETURNING {}",
+            target.table_ref,
+            column_names,
+            tuples.join(", "),
+            target.select_list()
+        );
+        statements.push(InsertStatement {
+            sql,
+            params,
+            cells,
+            rows: chunk.iter().map(|(index, _)| *index).collect(),
+        });
     }
 
-    Ok(deleted)
+    Ok(statements)
+}
+
+/// Inserts rows, returning them as stored, in order.
+async fn insert_batch(
+    conn: &mut PgConnection,
+    pool: &PgPool,
+    target: &EditTarget,
+    rows: &[&RowInsert],
+) -> Result<Vec<TableRow>, AppError> {
+    let mut inserted = Vec::with_capacity(rows.len());
+    for statement in insert_statements(target, rows, Params::default)? {
+        let result = statement
+            .params
+            .bind(sqlx::query(&statement.sql))
+            .fetch_all(&mut *conn)
+            .await;
+        match result {
+            Ok(rows) => inserted.extend(rows.iter().map(|row| target.table_row(row))),
+            Err(e) => {
+                return Err(locate_cell_error(pool, &target.table_ref, e, &statement.cells).await)
+            }
+        }
+    }
+
+    Ok(inserted)
+}
+
+/// Builds the `UPDATE` of one cell, returning the row as written.
+fn update_statement(
+    target: &EditTarget,
+    update: &RowUpdate,
+    params: &mut Params,
+) -> Result<String, AppError> {
+    let key = target.key("update")?;
+
+    // Validate column name
+    let column = find_column(&target.columns, &update.column)?;
+    let safe_column = safe_identifier(&column.name)?;
+
+    let new_value = params.push(column, param_text(column, &update.new_value)?);
+    let mut conditions = vec![key.where_clause(&update.row_id, &target.columns, params)?];
+    conditions.extend(version_conditions(
+        update.xmin.as_deref(),
+        update.original_values.as_ref(),
+        &target.columns,
+        params,
+    )?);
+
+    // The written row may have a new id, e.g. a new ctid
+    Ok(format!(
+        "UPDATE {} SET {} = {}{} RETURNING {}",
+        target.table_ref,
+        safe_column,
+        new_value,
+        where_clause(&conditions),
+        key.select_list()
+    ))
+}
+
+/// Updates one cell, returning the row before and as written, or `None` if
+/// no row matched.
+///
+/// The row is locked while its before-image is read, so `conn` must be in
+/// a transaction for the image to be the version updated.
+async fn update_row(
+    conn: &mut PgConnection,
+    target: &EditTarget,
+    update: &RowUpdate,
+) -> Result<Option<RowImage>, AppError> {
+    let mut params = Params::default();
+    let query = update_statement(target, update, &mut params)?;
+
+    let Some(before) = select_row(conn, target, &update.row_id, " FOR UPDATE").await? else {
+        return Ok(None);
+    };
+    let row = params
+        .bind(sqlx::query(&query))
+        .fetch_optional(&mut *conn)
+        .await
+        .map_err(|e| cell_error(e, &update.column))?;
+
+    Ok(row.map(|row| RowImage {
+        before: Some(before),
+        after: Some(target.table_row(&row)),
+    }))
+}
+
+/// Builds the `DELETE` of one row.
+fn delete_statement(
+    target: &EditTarget,
+    delete: &RowDelete,
+    params: &mut Params,
+) -> Result<String, AppError> {
+    let key = target.key("delete from")?;
+
+    let mut conditions = vec![key.where_clause(&delete.row_id, &target.columns, params)?];
+    conditions.extend(version_conditions(
+        delete.xmin.as_deref(),
+        delete.original_values.as_ref(),
+        &target.columns,
+        params,
+    )?);
+
+    // The deleted row is kept for undo
+    Ok(format!(
+        "DELETE FROM {}{} RETURNING {}",
+        target.table_ref,
+        where_clause(&conditions),
+        key.select_list()
+    ))
+}
+
+/// Deletes one row, returning it, or `None` if no row matched.
+async fn delete_row(
+    conn: &mut PgConnection,
+    target: &EditTarget,
+    delete: &RowDelete,
+) -> Result<Option<TableRow>, AppError> {
+    let mut params = Params::default();
+    let query = delete_statement(target, delete, &mut params)?;
+
+    let row = params
+        .bind(sqlx::query(&query))
+        .fetch_optional(&mut *conn)
+        .await?;
+    Ok(row.map(|row| target.table_row(&row)))
+}
+
+/// Builds a statement both with placeholders and with inlined values.
+fn statement_preview(
+    kind: ChangeKind,
+    indexes: Vec<usize>,
+    build: impl Fn(&mut Params) -> Result<String, AppError>,
+) -> Result<StatementPreview, AppError> {
+    let mut params = Params::default();
+    let parameterized_sql = build(&mut params)?;
+    let sql = build(&mut Params::inline())?;
+
+    Ok(StatementPreview {
+        kind,
+        indexes,
+        sql,
+        parameterized_sql,
+        params: params.values().to_vec(),
+    })
+}
+
+/// Loads the row identified by `row_id` as it is now, if it still exists.
+async fn current_row(
+    conn: &mut PgConnection,
+    target: &EditTarget,
+    row_id: &str,
+) -> Result<Option<TableRow>, AppError> {
+    select_row(conn, target, row_id, "").await
+}
+
+/// Loads the row identified by `row_id`, with a locking clause such as
+/// ` FOR UPDATE`.
+async fn select_row(
+    conn: &mut PgConnection,
+    target: &EditTarget,
+    row_id: &str,
+    locking: &str,
+) -> Result<Option<TableRow>, AppError> {
+    let key = target.key("read from")?;
+
+    let mut params = Params::default();
+    let query = format!(
+        "SELECT {} FROM {} WHERE {}{}",
+        key.select_list(),
+        target.table_ref,
+        key.where_clause(row_id, &target.columns, &mut params)?,
+        locking
+    );
+    let row = params
+        .bind(sqlx::query(&query))
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    Ok(row.map(|row| target.table_row(&row)))
 }
 
 // Helper functions
@@ -270,36 +1154,7 @@ async fn get_column_info(
     schema: &str,
     table: &str,
 ) -> Result<Vec<TableColumnInfo>, AppError> {
-    let query = r#"
-        SELECT
-            c.column_name,
-            c.data_type,
-            c.is_nullable,
-            c.column_default,
-            CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_primary_key,
-            CASE
-                WHEN c.column_default LIKE 'nextval%' THEN true
-                WHEN c.is_generated = 'ALWAYS' THEN true
-                WHEN c.identity_generation IS NOT NULL THEN true
-                ELSE false
-            END as is_auto_generated
-        FROM information_schema.columns c
-        LEFT JOIN (
-            SELECT kcu.column_name
-            FROM information_schema.table_constraints tc
-            JOIN information_schema.key_column_usage kcu
-                ON tc.constraint_name = kcu.constraint_name
-                AND tc.table_schema = kcu.table_schema
-            WHERE tc.constraint_type = 'PRIMARY KEY'
-                AND tc.table_schema = $1
-                AND tc.table_name = $2
-        ) pk ON c.column_name = pk.column_name
-        WHERE c.table_schema = $1
-          AND c.table_name = $2
-        ORDER BY c.ordinal_position
-    "#;
-
-    let rows = sqlx::query(query)
+    let rows = sqlx::query(queries::GET_COLUMNS)
         .bind(schema)
         .bind(table)
         .fetch_all(pool)
@@ -312,6 +1167,7 @@ async fn get_column_info(
             TableColumnInfo {
                 name: row.try_get("column_name").unwrap_or_default(),
                 data_type: row.try_get("data_type").unwrap_or_default(),
+                sql_type: row.try_get("sql_type").unwrap_or_default(),
                 is_nullable: is_nullable == "YES",
                 is_primary_key: row.try_get("is_primary_key").unwrap_or(false),
                 is_auto_generated: row.try_get("is_auto_generated").unwrap_or(false),
@@ -323,49 +1179,155 @@ async fn get_column_info(
     Ok(columns)
 }
 
-fn build_row_id(row: &PgRow, primary_keys: &[String]) -> String {
-    let mut pk_map: HashMap<String, serde_json::Value> = HashMap::new();
+/// Joins conditions into ` WHERE a AND b`, or an empty string if there are
+/// none.
+fn where_clause(conditions: &[String]) -> String {
+    if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    }
+}
 
-    for pk in primary_keys {
-        // Find column index by name
-        if let Some(col_idx) = row.columns().iter().position(|c| c.name() == pk) {
-            let col = &row.columns()[col_idx];
-            let type_name = col.type_info().to_string();
-            let value = get_column_value(row, col_idx, &type_name);
-            pk_map.insert(pk.clone(), value);
-        }
+/// Conditions that only hold while a row is unchanged since it was loaded.
+///
+/// `xmin` changes with every write to the row; original values are compared
+/// with [`value_matches`].
+fn version_conditions(
+    xmin: Option<&str>,
+    original_values: Option<&HashMap<String, serde_json::Value>>,
+    columns: &[TableColumnInfo],
+    params: &mut Params,
+) -> Result<Vec<String>, AppError> {
+    let mut conditions = Vec::new();
+
+    if let Some(xmin) = xmin {
+        conditions.push(format!(
+            "xmin::text = {}",
+            params.push_text(xmin.to_string())
+        ));
     }
 
-    serde_json::to_string(&pk_map).unwrap_or_default()
+    for (name, value) in original_values.into_iter().flatten() {
+        let column = find_column(columns, name)?;
+        conditions.push(value_matches(column, value, params)?);
+    }
+
+    Ok(conditions)
 }
 
-fn bind_json_value<'q>(
-    query: sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments>,
-    value: &'q serde_json::Value,
-) -> sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments> {
-    match value {
-        serde_json::Value::Null => query.bind(None::<String>),
-        serde_json::Value::Bool(b) => query.bind(*b),
-        serde_json::Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                query.bind(i)
-            } else if let Some(f) = n.as_f64() {
-                query.bind(f)
-            } else {
-                query.bind(n.to_string())
-            }
-        }
-        serde_json::Value::String(s) => {
-            // Handle NULL string
-            if s.eq_ignore_ascii_case("null") {
-                query.bind(None::<String>)
-            } else if s.starts_with('\'') && s.ends_with('\'') && s.len() > 2 {
-                // Handle quoted strings (for literal 'NULL')
-                query.bind(s[1..s.len() - 1].to_string())
-            } else {
-                query.bind(s.clone())
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RowKeyKind;
+    use serde_json::json;
+
+    fn column(name: &str, sql_type: &str, is_primary_key: bool) -> TableColumnInfo {
+        TableColumnInfo {
+            name: name.to_string(),
+            data_type: sql_type.to_string(),
+            sql_type: sql_type.to_string(),
+            is_nullable: !is_primary_key,
+            is_primary_key,
+            is_auto_generated: false,
+            default_value: None,
         }
-        serde_json::Value::Array(_) | serde_json::Value::Object(_) => query.bind(value.to_string()),
+    }
+
+    #[test]
+    fn test_preview_edits_of_one_row() {
+        let target = EditTarget {
+            table_ref: "\"public\".\"t\"".to_string(),
+            kind: RelationKind::Table,
+            columns: vec![column("id", "integer", true), column("name", "text", false)],
+            key: Some(RowKey {
+                kind: RowKeyKind::PrimaryKey,
+                columns: vec!["id".to_string()],
+            }),
+            format: ValueFormat::default(),
+        };
+        let row_id = r#"{"kind":"primary_key","values":{"id":1}}"#;
+        let changes: ChangeSet = serde_json::from_value(json!({
+            "schema": "public",
+            "table": "t",
+            "updates": [
+                {"row_id": row_id, "column": "id", "new_value": 2, "xmin": "7"},
+                {"row_id": row_id, "column": "name", "new_value": "x", "xmin": "7"}
+            ],
+            "deletes": [{"row_id": row_id, "xmin": "7"}]
+        }))
+        .unwrap();
+
+        let preview = change_preview(&target, &changes).unwrap();
+        let sql: Vec<&str> = preview.statements.iter().map(|s| s.sql.as_str()).collect();
+        assert_eq!(
+            sql,
+            [
+                "UPDATE \"public\".\"t\" SET \"id\" = '2'::integer \
+                 WHERE \"id\" = '1'::integer AND xmin::text = '7'::text \
+                 RETURNING *, xmin::text AS xmin",
+                // The row is addressed by its new id, without the version it
+                // was loaded with
+                "UPDATE \"public\".\"t\" SET \"name\" = 'x'::text \
+                 WHERE \"id\" = '2'::integer RETURNING *, xmin::text AS xmin",
+                "DELETE FROM \"public\".\"t\" WHERE \"id\" = '2'::integer \
+                 RETURNING *, xmin::text AS xmin",
+            ]
+        );
+    }
+
+    /// Run with `cargo test -- --ignored` and `TABLEX_TEST_DATABASE_URL`
+    /// pointing at a scratch database.
+    #[tokio::test]
+    #[ignore = "requires a local PostgreSQL"]
+    async fn test_update_two_cells_of_one_row() {
+        use sqlx::Connection;
+
+        let url = std::env::var("TABLEX_TEST_DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost:5432/postgres".to_string());
+        let mut conn = PgConnection::connect(&url).await.unwrap();
+        sqlx::query("CREATE TABLE pg_temp.t (id integer PRIMARY KEY, name text)")
+            .execute(&mut conn)
+            .await
+            .unwrap();
+        let xmin: String =
+            sqlx::query_scalar("INSERT INTO pg_temp.t VALUES (1, 'a') RETURNING xmin::text")
+                .fetch_one(&mut conn)
+                .await
+                .unwrap();
+        let target = EditTarget {
+            table_ref: "pg_temp.t".to_string(),
+            kind: RelationKind::Table,
+            columns: vec![column("id", "integer", true), column("name", "text", false)],
+            key: Some(RowKey {
+                kind: RowKeyKind::PrimaryKey,
+                columns: vec!["id".to_string()],
+            }),
+            format: ValueFormat::default(),
+        };
+        // Both edits carry the version the row was loaded with
+        let row_id = r#"{"kind":"primary_key","values":{"id":1}}"#;
+        let updates: Vec<RowUpdate> = serde_json::from_value(json!([
+            {"row_id": row_id, "column": "id", "new_value": 2, "xmin": xmin},
+            {"row_id": row_id, "column": "name", "new_value": "b", "xmin": xmin}
+        ]))
+        .unwrap();
+
+        let mut images = Vec::new();
+        update_each(&mut conn, &target, &updates, &mut images)
+            .await
+            .unwrap();
+
+        let row: (i32, String) = sqlx::query_as("SELECT id, name FROM pg_temp.t")
+            .fetch_one(&mut conn)
+            .await
+            .unwrap();
+        assert_eq!(row, (2, "b".to_string()));
+        // One image from before the first edit to after the last
+        assert_eq!(images.len(), 1);
+        let before = images[0].before.as_ref().unwrap();
+        let after = images[0].after.as_ref().unwrap();
+        assert_eq!(before.values, [json!(1), json!("a")]);
+        assert_eq!(after.values, [json!(2), json!("b")]);
     }
 }
@@ -0,0 +1,72 @@
+use crate::error::AppError;
+use crate::state::AppState;
+use crate::types::TransactionStatus;
+use tauri::State;
+
+/// Opens a transaction on the connection's SQL editor session.
+#[tauri::command]
+pub async fn begin_transaction(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<TransactionStatus, AppError> {
+    let db = state.connection(&connection_id).await?;
+    let mut session = db.session().await?;
+
+    if session.status == TransactionStatus::Unknown {
+        session.refresh_status().await;
+    }
+    match session.status {
+        TransactionStatus::Idle => {}
+        TransactionStatus::Unknown => {
+            return Err(AppError::Transaction(
+                "Cannot tell whether a transaction is open".to_string(),
+            ))
+        }
+        _ => {
+            return Err(AppError::Transaction(
+                "A transaction is already open".to_string(),
+            ))
+        }
+    }
+
+    session.control("BEGIN").await?;
+    Ok(session.status)
+}
+
+/// Commits the session's transaction. A failed transaction is rolled back
+/// by the server instead.
+#[tauri::command]
+pub async fn commit_transaction(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<TransactionStatus, AppError> {
+    let db = state.connection(&connection_id).await?;
+    let mut session = db.session().await?;
+
+    session.control("COMMIT").await?;
+    Ok(session.status)
+}
+
+#[tauri::command]
+pub async fn rollback_transaction(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<TransactionStatus, AppError> {
+    let db = state.connection(&connection_id).await?;
+    let mut session = db.session().await?;
+
+    session.control("ROLLBACK").await?;
+    Ok(session.status)
+}
+
+/// Returns the session's transaction status as of its last statement.
+#[tauri::command]
+pub async fn get_transaction_status(
+    connection_id: String,
+    state: State<'_, AppState>,
+) -> Result<TransactionStatus, AppError> {
+    let db = state.connection(&connection_id).await?;
+    let session = db.session().await?;
+
+    Ok(session.status)
+}
@@ -0,0 +1,419 @@
+//! Type-aware binding of JSON cell values to table columns
+//!
+//! Values are sent as text and cast to the column type in SQL
+//! (`$1::uuid`), so PostgreSQL's own input functions parse them. This works
+//! for every type with a text representation, including enums, domains and
+//! arrays, without a Rust encoder per type.
+
+use crate::db::sql_utils::quote_literal;
+use crate::error::AppError;
+use crate::types::TableColumnInfo;
+use serde_json::Value;
+use sqlx::postgres::{PgArguments, PgDatabaseError};
+use sqlx::query::Query;
+use sqlx::{PgPool, Postgres};
+
+/// SQLSTATE class `data_exception`: the value doesn't fit the type
+const DATA_EXCEPTION_CLASS: &str = "22";
+
+/// SQLSTATE `not_null_violation`
+const NOT_NULL_VIOLATION: &str = "23502";
+
+/// Positional parameters of a statement, all bound as text.
+#[derive(Debug, Default)]
+pub struct Params {
+    values: Vec<Option<String>>,
+    inline: bool,
+}
+
+impl Params {
+    /// Parameters written into the statement as literals instead of
+    /// placeholders, for displaying a statement rather than running it.
+    pub fn inline() -> Self {
+        Self {
+            values: Vec::new(),
+            inline: true,
+        }
+    }
+
+    /// Adds a value for `column` and returns its placeholder, cast to the
+    /// column type.
+    pub fn push(&mut self, column: &TableColumnInfo, text: Option<String>) -> String {
+        let placeholder = if self.inline {
+            format!("{}::{}", literal(text.as_deref()), column.sql_type)
+        } else {
+            cast_placeholder(self.values.len() + 1, column)
+        };
+        self.values.push(text);
+        placeholder
+    }
+
+    /// Adds a value compared as text, e.g. a `LIKE` pattern.
+    pub fn push_text(&mut self, text: String) -> String {
+        let placeholder = if self.inline {
+            format!("{}::text", quote_literal(&text))
+        } else {
+            format!("${}::text", self.values.len() + 1)
+        };
+        self.values.push(Some(text));
+        placeholder
+    }
+
+    pub fn bind<'q>(
+        &self,
+        query: Query<'q, Postgres, PgArguments>,
+    ) -> Query<'q, Postgres, PgArguments> {
+        self.values
+            .iter()
+            .fold(query, |query, value| query.bind(value.clone()))
+    }
+
+    pub fn values(&self) -> &[Option<String>] {
+        &self.values
+    }
+}
+
+fn literal(text: Option<&str>) -> String {
+    text.map_or_else(|| "NULL".to_string(), quote_literal)
+}
+
+/// Returns a placeholder cast to the column's type, e.g. `$2::numeric`.
+pub fn cast_placeholder(index: usize, column: &TableColumnInfo) -> String {
+    format!("${}::{}", index, column.sql_type)
+}
+
+/// Looks up a column of the table by name.
+pub fn find_column<'a>(
+    columns: &'a [TableColumnInfo],
+    name: &str,
+) -> Result<&'a TableColumnInfo, AppError> {
+    columns
+        .iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| AppError::UnknownColumn(name.to_string()))
+}
+
+/// Converts a JSON cell value into the text form parsed by the column's type.
+///
+/// `None` is SQL NULL. Strings are passed through unchanged, so a string
+/// `"null"` is the text `null`. JSON arrays are accepted for array columns
+/// and objects only for `json`/`jsonb` columns; for those, strings are
+/// treated as JSON text.
+pub fn param_text(column: &TableColumnInfo, value: &Value) -> Result<Option<String>, AppError> {
+    let is_json = matches!(column.sql_type.as_str(), "json" | "jsonb");
+    let is_array = column.sql_type.ends_with("[]");
+
+    let text = match value {
+        Value::Null => return Ok(None),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.clone(),
+        Value::Array(_) | Value::Object(_) if is_json => value.to_string(),
+        Value::Array(items) if is_array => array_literal(items),
+        Value::Array(_) => return Err(invalid_shape(column, "an array")),
+        Value::Object(_) => return Err(invalid_shape(column, "an object")),
+    };
+
+    Ok(Some(text))
+}
+
+fn invalid_shape(column: &TableColumnInfo, shape: &str) -> AppError {
+    AppError::InvalidValue {
+        column: column.name.clone(),
+        message: format!(
+            "expected a value of type {}, got {}",
+            column.sql_type, shape
+        ),
+    }
+}
+
+/// Formats a JSON array as a PostgreSQL array literal such as
+/// `{1,NULL,"a \"b\""}`.
+fn array_literal(items: &[Value]) -> String {
+    let elements: Vec<String> = items
+        .iter()
+        .map(|item| match item {
+            Value::Null => "NULL".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => quote_array_element(s),
+            Value::Array(inner) => array_literal(inner),
+            Value::Object(_) => quote_array_element(&item.to_string()),
+        })
+        .collect();
+
+    format!("{{{}}}", elements.join(","))
+}
+
+fn quote_array_element(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Attributes a failed write to a cell when the error is about a value.
+///
+/// Data exceptions are blamed on `column`, the only column whose value was
+/// supplied by the user; `NOT NULL` violations name their own column.
+pub fn cell_error(e: sqlx::Error, column: &str) -> AppError {
+    let sqlx::Error::Database(db_err) = &e else {
+        return e.into();
+    };
+    let code = db_err.code().unwrap_or_default();
+
+    let column = if code.starts_with(DATA_EXCEPTION_CLASS) {
+        column.to_string()
+    } else if code == NOT_NULL_VIOLATION {
+        match db_err
+            .try_downcast_ref::<PgDatabaseError>()
+            .and_then(|pg| pg.column())
+        {
+            Some(column) => column.to_string(),
+            None => return e.into(),
+        }
+    } else {
+        return e.into();
+    };
+
+    AppError::InvalidValue {
+        column,
+        message: db_err.message().to_string(),
+    }
+}
+
+/// Finds the cell responsible for a failed multi-column write.
+///
+/// Each value is parsed on its own with `jsonb_populate_record`, which runs
+/// the column type's input function with its modifier, so an over-long
+/// `varchar` or an overflowing `numeric(p, s)` fails the same way as in the
+/// write. No rows, defaults or triggers are touched. Returns the original
+/// error if no single value is invalid.
+pub async fn locate_cell_error(
+    pool: &PgPool,
+    table_ref: &str,
+    e: sqlx::Error,
+    values: &[(&TableColumnInfo, Option<String>)],
+) -> AppError {
+    let is_data_exception = matches!(
+        &e,
+        sqlx::Error::Database(db_err)
+            if db_err.code().unwrap_or_default().starts_with(DATA_EXCEPTION_CLASS)
+    );
+    if !is_data_exception {
+        return cell_error(e, "");
+    }
+
+    let probe = format!(
+        "SELECT jsonb_populate_record(NULL::{}, jsonb_build_object($1::text, $2::text))",
+        table_ref
+    );
+    for (column, text) in values {
+        if text.is_none() {
+            continue;
+        }
+        let result = sqlx::query(&probe)
+            .bind(&column.name)
+            .bind(text)
+            .execute(pool)
+            .await;
+        if let Err(probe_err) = result {
+            return cell_error(probe_err, &column.name);
+        }
+    }
+
+    e.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(sql_type: &str) -> TableColumnInfo {
+        TableColumnInfo {
+            name: "col".to_string(),
+            data_type: sql_type.to_string(),
+            sql_type: sql_type.to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_auto_generated: false,
+            default_value: None,
+        }
+    }
+
+    #[test]
+    fn test_scalar_param_text() {
+        let text = column("text");
+        assert_eq!(param_text(&text, &json!(null)).unwrap(), None);
+        assert_eq!(
+            param_text(&text, &json!("null")).unwrap().as_deref(),
+            Some("null")
+        );
+        assert_eq!(
+            param_text(&column("numeric"), &json!("12.50"))
+                .unwrap()
+                .as_deref(),
+            Some("12.50")
+        );
+        assert_eq!(
+            param_text(&column("bigint"), &json!(9007199254740993i64))
+                .unwrap()
+                .as_deref(),
+            Some("9007199254740993")
+        );
+        assert_eq!(
+            param_text(&column("boolean"), &json!(true))
+                .unwrap()
+                .as_deref(),
+            Some("true")
+        );
+    }
+
+    #[test]
+    fn test_json_and_array_param_text() {
+        assert_eq!(
+            param_text(&column("jsonb"), &json!({"a": [1, null]}))
+                .unwrap()
+                .as_deref(),
+            Some(r#"{"a":[1,null]}"#)
+        );
+        assert_eq!(
+            param_text(&column("integer[]"), &json!([[1, 2], [3, null]]))
+                .unwrap()
+                .as_deref(),
+            Some("{{1,2},{3,NULL}}")
+        );
+        assert_eq!(
+            param_text(&column("text[]"), &json!(["a \"b\"", "c\\d", "NULL"]))
+                .unwrap()
+                .as_deref(),
+            Some(r#"{"a \"b\"","c\\d","NULL"}"#)
+        );
+    }
+
+    #[test]
+    fn test_invalid_shapes() {
+        assert!(matches!(
+            param_text(&column("integer"), &json!([1])),
+            Err(AppError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            param_text(&column("text[]"), &json!({"a": 1})),
+            Err(AppError::InvalidValue { .. })
+        ));
+    }
+
+    #[test]
+    fn test_inline_params() {
+        let mut params = Params::inline();
+        assert_eq!(
+            params.push(&column("text"), Some("it's".to_string())),
+            "'it''s'::text"
+        );
+        assert_eq!(params.push(&column("integer"), None), "NULL::integer");
+        assert_eq!(params.push_text("(0,1)".to_string()), "'(0,1)'::text");
+        assert_eq!(
+            params.values(),
+            [Some("it's".to_string()), None, Some("(0,1)".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_cast_placeholder() {
+        assert_eq!(
+            cast_placeholder(3, &column("public.mood")),
+            "$3::public.mood"
+        );
+    }
+
+    /// Run with `cargo test -- --ignored` and `TABLEX_TEST_DATABASE_URL`
+    /// pointing at a scratch database.
+    #[tokio::test]
+    #[ignore = "requires a local PostgreSQL"]
+    async fn test_round_trip_with_type_modifier() {
+        use crate::db::queries;
+        use sqlx::{Connection, PgConnection, Row};
+
+        let url = std::env::var("TABLEX_TEST_DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost:5432/postgres".to_string());
+        let mut conn = PgConnection::connect(&url).await.unwrap();
+        let mut tx = conn.begin().await.unwrap();
+        sqlx::query(
+            "CREATE TABLE pg_temp.modifiers \
+             (c char(5), b bit(4), ca char(3)[], ba bit(2)[])",
+        )
+        .execute(&mut *tx)
+        .await
+        .unwrap();
+        sqlx::query("INSERT INTO pg_temp.modifiers DEFAULT VALUES")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        let columns: Vec<TableColumnInfo> = sqlx::query(queries::GET_COLUMNS)
+            .bind("pg_temp")
+            .bind("modifiers")
+            .fetch_all(&mut *tx)
+            .await
+            .unwrap()
+            .iter()
+            .map(|row| {
+                let sql_type: String = row.get("sql_type");
+                TableColumnInfo {
+                    name: row.get("column_name"),
+                    ..column(&sql_type)
+                }
+            })
+            .collect();
+
+        for (name, value, stored) in [
+            ("c", json!("ab"), "ab   "),
+            ("c", json!("abcde"), "abcde"),
+            ("b", json!("1010"), "1010"),
+            ("ca", json!(["x", "abc"]), "{\"x  \",abc}"),
+            ("ba", json!(["10", "01"]), "{10,01}"),
+        ] {
+            let column = find_column(&columns, name).unwrap();
+            let mut params = Params::default();
+            let set = format!(
+                "UPDATE pg_temp.modifiers SET {} = {}",
+                name,
+                params.push(column, param_text(column, &value).unwrap())
+            );
+            params
+                .bind(sqlx::query(&set))
+                .execute(&mut *tx)
+                .await
+                .unwrap();
+
+            let mut params = Params::default();
+            let select = format!(
+                "SELECT format('%s', {}) FROM pg_temp.modifiers WHERE {} = {}",
+                name,
+                name,
+                params.push(column, param_text(column, &value).unwrap())
+            );
+            let text: String = params
+                .bind(sqlx::query(&select))
+                .fetch_one(&mut *tx)
+                .await
+                .unwrap_or_else(|e| panic!("{} = {} not found: {}", name, value, e))
+                .get(0);
+            assert_eq!(text, stored, "{} = {}", name, value);
+        }
+
+        // Values that don't fit are rejected rather than truncated
+        for (name, value) in [("c", json!("abcdef")), ("b", json!("101"))] {
+            let column = find_column(&columns, name).unwrap();
+            let mut params = Params::default();
+            let set = format!(
+                "UPDATE pg_temp.modifiers SET {} = {}",
+                name,
+                params.push(column, param_text(column, &value).unwrap())
+            );
+            let mut savepoint = tx.begin().await.unwrap();
+            let result = params
+                .bind(sqlx::query(&set))
+                .execute(&mut *savepoint)
+                .await;
+            assert!(result.is_err(), "{} = {} was accepted", name, value);
+        }
+    }
+}
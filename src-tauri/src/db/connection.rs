@@ -1,46 +1,271 @@
+use super::session::Session;
+use super::ssh_tunnel::SshTunnel;
+use super::undo::UndoLog;
+use crate::error::AppError;
 use crate::types::{ConnectionConfig, SslMode};
-use sqlx::postgres::PgPoolOptions;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions, PgSslMode};
 use sqlx::PgPool;
+use std::path::Path;
+use tokio::sync::{MappedMutexGuard, Mutex, MutexGuard};
 
 impl ConnectionConfig {
-    pub fn connection_string(&self) -> String {
+    pub fn connect_options(&self) -> Result<PgConnectOptions, AppError> {
+        self.connect_options_for(&self.host, self.port)
+    }
+
+    /// Builds connect options that reach the database through `host:port`,
+    /// e.g. the local end of an SSH tunnel.
+    ///
+    /// `verify-full` is rejected with an SSH tunnel: the certificate would be
+    /// checked against the tunnel's local address, not the database host.
+    pub fn connect_options_for(&self, host: &str, port: u16) -> Result<PgConnectOptions, AppError> {
+        if self.ssh.is_some() && matches!(self.ssl_mode, SslMode::VerifyFull) {
+            return Err(AppError::InvalidConfig(
+                "SSL mode verify-full can't check the host name through an SSH tunnel; \
+                 use verify-ca instead"
+                    .to_string(),
+            ));
+        }
+
         let ssl_mode = match self.ssl_mode {
-            SslMode::Disable => "disable",
-            SslMode::Prefer => "prefer",
-            SslMode::Require => "require",
+            SslMode::Disable => PgSslMode::Disable,
+            SslMode::Allow => PgSslMode::Allow,
+            SslMode::Prefer => PgSslMode::Prefer,
+            SslMode::Require => PgSslMode::Require,
+            SslMode::VerifyCa => PgSslMode::VerifyCa,
+            SslMode::VerifyFull => PgSslMode::VerifyFull,
         };
 
-        format!(
-            "postgres://{}:{}@{}:{}/{}?sslmode={}",
-            self.username, self.password, self.host, self.port, self.database, ssl_mode
-        )
+        let mut options = PgConnectOptions::new()
+            .host(host)
+            .port(port)
+            .username(&self.username)
+            .password(&self.password)
+            .database(&self.database)
+            .ssl_mode(ssl_mode);
+
+        if let Some(path) = non_empty(&self.ssl_root_cert) {
+            options = options.ssl_root_cert(certificate_file(path, "root certificate")?);
+        }
+
+        match (non_empty(&self.ssl_cert), non_empty(&self.ssl_key)) {
+            (Some(cert), Some(key)) => {
+                options = options
+                    .ssl_client_cert(certificate_file(cert, "client certificate")?)
+                    .ssl_client_key(certificate_file(key, "client key")?);
+            }
+            (None, None) => {}
+            _ => {
+                return Err(AppError::InvalidConfig(
+                    "Client certificate and key must be given together".to_string(),
+                ))
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+fn non_empty(value: &Option<String>) -> Option<&str> {
+    value.as_deref().filter(|v| !v.trim().is_empty())
+}
+
+fn certificate_file<'a>(path: &'a str, kind: &str) -> Result<&'a str, AppError> {
+    if Path::new(path).is_file() {
+        Ok(path)
+    } else {
+        Err(AppError::Certificate(format!(
+            "{} not found: {}",
+            kind, path
+        )))
+    }
+}
+
+/// TLS failures are almost always certificate problems (untrusted CA,
+/// hostname mismatch, unreadable key), so report them separately.
+fn connect_error(e: sqlx::Error) -> AppError {
+    match e {
+        sqlx::Error::Tls(err) => AppError::Certificate(err.to_string()),
+        other => other.into(),
     }
 }
 
 pub struct DatabaseConnection {
     pub config: ConnectionConfig,
     pub pool: PgPool,
+    tunnel: Option<SshTunnel>,
+    /// Pinned connection for the SQL editor, opened on first use
+    session: Mutex<Option<Session>>,
+    /// Data edits applied through the table editor, for undo
+    pub undo_log: Mutex<UndoLog>,
 }
 
 impl DatabaseConnection {
-    pub async fn connect(config: ConnectionConfig) -> Result<Self, sqlx::Error> {
-        let pool = PgPoolOptions::new()
-            .max_connections(5)
-            .connect(&config.connection_string())
-            .await?;
+    pub async fn connect(config: ConnectionConfig) -> Result<Self, AppError> {
+        let (pool, tunnel) = open_pool(&config, 5).await?;
 
-        Ok(Self { config, pool })
+        Ok(Self {
+            config,
+            pool,
+            tunnel,
+            session: Mutex::new(None),
+            undo_log: Mutex::new(UndoLog::default()),
+        })
     }
 
-    pub async fn test_connection(config: &ConnectionConfig) -> Result<(), sqlx::Error> {
-        let pool = PgPoolOptions::new()
-            .max_connections(1)
-            .connect(&config.connection_string())
-            .await?;
+    /// Locks the pinned session, opening it on first use.
+    ///
+    /// The connection is detached from the pool so it never serves other
+    /// commands and the pool can open a replacement.
+    pub async fn session(&self) -> Result<MappedMutexGuard<'_, Session>, AppError> {
+        let mut guard = self.session.lock().await;
+        if guard.is_none() {
+            let conn = self.pool.acquire().await?.detach();
+            *guard = Some(Session::open(conn).await?);
+        }
 
-        sqlx::query("SELECT 1").execute(&pool).await?;
+        Ok(MutexGuard::map(guard, |session| {
+            session.as_mut().expect("session was just opened")
+        }))
+    }
+
+    pub async fn test_connection(config: &ConnectionConfig) -> Result<(), AppError> {
+        let (pool, tunnel) = open_pool(config, 1).await?;
+
+        let result = sqlx::query("SELECT 1").execute(&pool).await;
         pool.close().await;
+        if let Some(tunnel) = tunnel {
+            tunnel.close().await;
+        }
+        result?;
 
         Ok(())
     }
+
+    /// Closes the session, the pool and the SSH tunnel, if any.
+    pub async fn disconnect(&self) {
+        if let Some(session) = self.session.lock().await.take() {
+            session.close().await;
+        }
+        self.pool.close().await;
+        if let Some(tunnel) = &self.tunnel {
+            tunnel.close().await;
+        }
+    }
+}
+
+async fn open_pool(
+    config: &ConnectionConfig,
+    max_connections: u32,
+) -> Result<(PgPool, Option<SshTunnel>), AppError> {
+    let tunnel = match &config.ssh {
+        Some(ssh) => Some(SshTunnel::open(ssh, &config.host, config.port).await?),
+        None => None,
+    };
+
+    let options = match &tunnel {
+        Some(tunnel) => config.connect_options_for("127.0.0.1", tunnel.local_port()),
+        None => config.connect_options(),
+    };
+
+    let pool = match options {
+        Ok(options) => PgPoolOptions::new()
+            .max_connections(max_connections)
+            .connect_with(options)
+            .await
+            .map_err(connect_error),
+        Err(e) => Err(e),
+    };
+
+    match pool {
+        Ok(pool) => Ok((pool, tunnel)),
+        Err(e) => {
+            if let Some(tunnel) = tunnel {
+                tunnel.close().await;
+            }
+            Err(e)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::SshTunnelConfig;
+
+    fn config(ssl_mode: SslMode) -> ConnectionConfig {
+        ConnectionConfig {
+            id: "c".to_string(),
+            name: "c".to_string(),
+            host: "db.example.com".to_string(),
+            port: 5432,
+            database: "app".to_string(),
+            username: "app".to_string(),
+            password: String::new(),
+            ssl_mode,
+            ssl_root_cert: None,
+            ssl_cert: None,
+            ssl_key: None,
+            ssh: None,
+        }
+    }
+
+    fn ssh() -> SshTunnelConfig {
+        SshTunnelConfig {
+            host: "bastion".to_string(),
+            port: 22,
+            username: "me".to_string(),
+            auth_method: Default::default(),
+            private_key_path: None,
+            password: String::new(),
+            passphrase: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_ssl_mode_mapping() {
+        let cases = [
+            (SslMode::Disable, PgSslMode::Disable),
+            (SslMode::Allow, PgSslMode::Allow),
+            (SslMode::Prefer, PgSslMode::Prefer),
+            (SslMode::Require, PgSslMode::Require),
+            (SslMode::VerifyCa, PgSslMode::VerifyCa),
+            (SslMode::VerifyFull, PgSslMode::VerifyFull),
+        ];
+        for (mode, expected) in cases {
+            let options = config(mode).connect_options().unwrap();
+            // PgSslMode has no PartialEq
+            assert_eq!(
+                format!("{:?}", options.get_ssl_mode()),
+                format!("{:?}", expected)
+            );
+            assert_eq!(options.get_host(), "db.example.com");
+        }
+    }
+
+    #[test]
+    fn test_ssl_mode_through_ssh_tunnel() {
+        let mut verify_ca = config(SslMode::VerifyCa);
+        verify_ca.ssh = Some(ssh());
+        let options = verify_ca.connect_options_for("127.0.0.1", 40000).unwrap();
+        assert_eq!(options.get_host(), "127.0.0.1");
+        assert_eq!(options.get_port(), 40000);
+
+        let mut verify_full = config(SslMode::VerifyFull);
+        verify_full.ssh = Some(ssh());
+        assert!(matches!(
+            verify_full.connect_options_for("127.0.0.1", 40000),
+            Err(AppError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_client_certificate_requires_key() {
+        let mut half = config(SslMode::Require);
+        half.ssl_cert = Some("/nonexistent/client.crt".to_string());
+        assert!(matches!(
+            half.connect_options(),
+            Err(AppError::InvalidConfig(_))
+        ));
+    }
 }
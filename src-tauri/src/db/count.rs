@@ -0,0 +1,69 @@
+//! Row counts of table data
+//!
+//! An exact `COUNT(*)` reads every matching row, which takes minutes on
+//! very large tables. Pages are loaded with the planner's estimate instead,
+//! which costs no more than planning the query; exact counts are left to an
+//! explicit, cancellable request.
+
+use crate::db::binding::Params;
+use crate::error::AppError;
+use serde_json::Value;
+use sqlx::postgres::PgConnection;
+use sqlx::{Acquire, PgPool, Row};
+
+/// Asks the planner how many rows match `where_sql`.
+pub async fn estimate_count(
+    pool: &PgPool,
+    table_ref: &str,
+    where_sql: &str,
+    params: &Params,
+) -> Result<usize, AppError> {
+    let query = format!(
+        "EXPLAIN (FORMAT JSON) SELECT 1 FROM {}{}",
+        table_ref, where_sql
+    );
+    let row = params.bind(sqlx::query(&query)).fetch_one(pool).await?;
+
+    Ok(plan_rows(&row.try_get(0)?) as usize)
+}
+
+/// Counts rows matching `where_sql`, giving up after `timeout_ms`.
+pub async fn exact_count(
+    conn: &mut PgConnection,
+    table_ref: &str,
+    where_sql: &str,
+    params: &Params,
+    timeout_ms: u64,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = conn.begin().await?;
+    sqlx::query(&format!("SET LOCAL statement_timeout = {}", timeout_ms))
+        .execute(&mut *tx)
+        .await?;
+
+    let query = format!("SELECT COUNT(*) FROM {}{}", table_ref, where_sql);
+    let row = params.bind(sqlx::query(&query)).fetch_one(&mut *tx).await?;
+    tx.commit().await?;
+
+    row.try_get(0)
+}
+
+/// Reads the estimated row count of the top node of an `EXPLAIN (FORMAT
+/// JSON)` plan.
+fn plan_rows(plan: &Value) -> f64 {
+    plan.pointer("/0/Plan/Plan Rows")
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_plan_rows() {
+        let plan = json!([{"Plan": {"Node Type": "Seq Scan", "Plan Rows": 523000000}}]);
+        assert_eq!(plan_rows(&plan), 523000000.0);
+        assert_eq!(plan_rows(&json!([])), 0.0);
+    }
+}
@@ -0,0 +1,291 @@
+//! Keyset pagination cursors for table data
+//!
+//! A cursor holds the sort key values of a boundary row. The next page is
+//! found by seeking past those values instead of skipping rows with
+//! `OFFSET`, so deep pages cost the same as the first one when the sort key
+//! is indexed. The sort must end on a unique key (see
+//! [`effective_sort`](crate::db::sort::effective_sort)) for the seek to be
+//! exact.
+
+use crate::db::binding::{find_column, param_text, Params};
+use crate::db::row_utils::{get_column_value, ValueFormat};
+use crate::db::sql_utils::safe_identifier;
+use crate::error::AppError;
+use crate::types::{NullsOrder, SortDirection, SortKey, TableColumnInfo};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::postgres::PgRow;
+use sqlx::{Column, Row};
+
+/// A decoded cursor: the rows wanted are strictly after (or before) the
+/// boundary row in the sort order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub before: bool,
+    pub sort: Vec<SortKey>,
+    pub values: Vec<Value>,
+}
+
+impl Cursor {
+    /// Builds a cursor from the sort key values of `row`.
+    pub fn from_row(row: &PgRow, sort: &[SortKey], before: bool, format: ValueFormat) -> Self {
+        let values = sort
+            .iter()
+            .map(|key| {
+                row.columns()
+                    .iter()
+                    .position(|c| c.name() == key.column)
+                    .map(|idx| get_column_value(row, idx, format))
+                    .unwrap_or(Value::Null)
+            })
+            .collect();
+
+        Self {
+            before,
+            sort: sort.to_vec(),
+            values,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Decodes a cursor and checks it was made for the same sort order.
+    pub fn decode(encoded: &str, sort: &[SortKey]) -> Result<Self, AppError> {
+        let cursor: Cursor = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| AppError::InvalidCursor("malformed cursor".to_string()))?;
+
+        if cursor.sort != sort || cursor.values.len() != sort.len() {
+            return Err(AppError::InvalidCursor(
+                "cursor was made for a different sort order".to_string(),
+            ));
+        }
+        Ok(cursor)
+    }
+}
+
+/// Reverses a sort order, spelling out where NULLs go so they move to the
+/// other end too. Reading a page backwards uses the reversed order.
+pub fn reverse_sort(sort: &[SortKey]) -> Vec<SortKey> {
+    sort.iter()
+        .map(|key| SortKey {
+            column: key.column.clone(),
+            direction: match key.direction {
+                SortDirection::Asc => SortDirection::Desc,
+                SortDirection::Desc => SortDirection::Asc,
+            },
+            nulls: Some(if nulls_first(key) {
+                NullsOrder::Last
+            } else {
+                NullsOrder::First
+            }),
+        })
+        .collect()
+}
+
+/// Whether NULLs sort before other values, following PostgreSQL's default
+/// of NULLS LAST for ascending and NULLS FIRST for descending keys.
+fn nulls_first(key: &SortKey) -> bool {
+    match key.nulls {
+        Some(order) => order == NullsOrder::First,
+        None => key.direction == SortDirection::Desc,
+    }
+}
+
+/// Compiles the condition selecting rows strictly after `values` in the
+/// `sort` order.
+///
+/// When every key is ascending (or every key descending) and cannot be
+/// NULL, this is a row comparison such as `("a", "id") > ($1, $2)`, which
+/// PostgreSQL can answer from a matching index. Otherwise it expands to
+/// `a > $1 OR (a = $1 AND id > $2)`, with the NULL placement of each key
+/// spelled out.
+pub fn seek_clause(
+    sort: &[SortKey],
+    values: &[Value],
+    columns: &[TableColumnInfo],
+    params: &mut Params,
+) -> Result<String, AppError> {
+    let mut keys = Vec::with_capacity(sort.len());
+    for (key, value) in sort.iter().zip(values) {
+        let column = find_column(columns, &key.column)?;
+        let name = safe_identifier(&column.name)?;
+        let placeholder = param_text(column, value)?.map(|text| params.push(column, Some(text)));
+        keys.push((key, column, name, placeholder));
+    }
+
+    let uniform = keys
+        .iter()
+        .all(|(key, _, _, _)| key.direction == sort[0].direction);
+    let non_null = keys
+        .iter()
+        .all(|(_, column, _, placeholder)| !column.is_nullable && placeholder.is_some());
+    if uniform && non_null && !keys.is_empty() {
+        let op = match sort[0].direction {
+            SortDirection::Asc => ">",
+            SortDirection::Desc => "<",
+        };
+        let names: Vec<&str> = keys.iter().map(|(_, _, name, _)| name.as_str()).collect();
+        let placeholders: Vec<&str> = keys
+            .iter()
+            .filter_map(|(_, _, _, placeholder)| placeholder.as_deref())
+            .collect();
+        return Ok(format!(
+            "({}) {} ({})",
+            names.join(", "),
+            op,
+            placeholders.join(", ")
+        ));
+    }
+
+    let mut terms = Vec::new();
+    let mut equal = Vec::new();
+    for (key, column, name, placeholder) in &keys {
+        let after = match placeholder {
+            Some(p) => {
+                let op = match key.direction {
+                    SortDirection::Asc => ">",
+                    SortDirection::Desc => "<",
+                };
+                if nulls_first(key) || !column.is_nullable {
+                    Some(format!("{} {} {}", name, op, p))
+                } else {
+                    Some(format!("({} {} {} OR {} IS NULL)", name, op, p, name))
+                }
+            }
+            // Everything non-NULL follows a NULL only when NULLs come first
+            None if nulls_first(key) => Some(format!("{} IS NOT NULL", name)),
+            None => None,
+        };
+
+        if let Some(after) = after {
+            if equal.is_empty() {
+                terms.push(after);
+            } else {
+                terms.push(format!("({} AND {})", equal.join(" AND "), after));
+            }
+        }
+
+        equal.push(match placeholder {
+            Some(p) => format!("{} = {}", name, p),
+            None => format!("{} IS NULL", name),
+        });
+    }
+
+    if terms.is_empty() {
+        return Ok("FALSE".to_string());
+    }
+    Ok(format!("({})", terms.join(" OR ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(name: &str, is_nullable: bool) -> TableColumnInfo {
+        TableColumnInfo {
+            name: name.to_string(),
+            data_type: "integer".to_string(),
+            sql_type: "integer".to_string(),
+            is_nullable,
+            is_primary_key: !is_nullable,
+            is_auto_generated: false,
+            default_value: None,
+        }
+    }
+
+    fn key(column: &str, direction: SortDirection, nulls: Option<NullsOrder>) -> SortKey {
+        SortKey {
+            column: column.to_string(),
+            direction,
+            nulls,
+        }
+    }
+
+    #[test]
+    fn test_row_comparison_seek() {
+        let columns = vec![column("id", false)];
+        let sort = vec![key("id", SortDirection::Asc, None)];
+        let mut params = Params::default();
+
+        let sql = seek_clause(&sort, &[json!(42)], &columns, &mut params).unwrap();
+        assert_eq!(sql, "(\"id\") > ($1::integer)");
+
+        let sql = seek_clause(&reverse_sort(&sort), &[json!(42)], &columns, &mut params).unwrap();
+        assert_eq!(sql, "(\"id\") < ($2::integer)");
+    }
+
+    #[test]
+    fn test_expanded_seek_with_nulls() {
+        let columns = vec![column("name", true), column("id", false)];
+        let sort = vec![
+            key("name", SortDirection::Asc, None),
+            key("id", SortDirection::Asc, None),
+        ];
+
+        let mut params = Params::default();
+        let sql = seek_clause(&sort, &[json!("b"), json!(7)], &columns, &mut params).unwrap();
+        assert_eq!(
+            sql,
+            "((\"name\" > $1::integer OR \"name\" IS NULL) OR (\"name\" = $1::integer AND \"id\" > $2::integer))"
+        );
+
+        // NULLs sort last, so after a NULL name only higher ids remain
+        let mut params = Params::default();
+        let sql = seek_clause(&sort, &[Value::Null, json!(7)], &columns, &mut params).unwrap();
+        assert_eq!(sql, "((\"name\" IS NULL AND \"id\" > $1::integer))");
+
+        // Reading backwards, NULLs come first and non-NULL names precede them
+        let mut params = Params::default();
+        let sql = seek_clause(
+            &reverse_sort(&sort),
+            &[Value::Null, json!(7)],
+            &columns,
+            &mut params,
+        )
+        .unwrap();
+        assert_eq!(
+            sql,
+            "(\"name\" IS NOT NULL OR (\"name\" IS NULL AND \"id\" < $1::integer))"
+        );
+    }
+
+    #[test]
+    fn test_reverse_sort() {
+        let sort = vec![
+            key("a", SortDirection::Asc, None),
+            key("b", SortDirection::Desc, Some(NullsOrder::Last)),
+        ];
+        assert_eq!(
+            reverse_sort(&sort),
+            vec![
+                key("a", SortDirection::Desc, Some(NullsOrder::First)),
+                key("b", SortDirection::Asc, Some(NullsOrder::First)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let sort = vec![key("id", SortDirection::Asc, None)];
+        let cursor = Cursor {
+            before: true,
+            sort: sort.clone(),
+            values: vec![json!("9007199254740993")],
+        };
+
+        let encoded = cursor.encode();
+        assert_eq!(Cursor::decode(&encoded, &sort).unwrap(), cursor);
+
+        let other_sort = vec![key("id", SortDirection::Desc, None)];
+        assert!(Cursor::decode(&encoded, &other_sort).is_err());
+        assert!(Cursor::decode("not a cursor", &sort).is_err());
+    }
+}
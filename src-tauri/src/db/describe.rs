@@ -0,0 +1,77 @@
+use crate::types::ColumnMetadata;
+use sqlx::postgres::types::Oid;
+use sqlx::postgres::{PgConnection, PgTypeInfo};
+use sqlx::{Column, Executor, Statement, TypeInfo};
+use std::collections::HashMap;
+
+/// Describes the result columns of a statement without executing it.
+///
+/// The statement is prepared and cached on the connection, so executing it
+/// afterwards reuses the same server-side statement. This is used instead
+/// of sqlx's `describe`, whose nullability inference runs `EXPLAIN`: inside
+/// an open transaction a failing `EXPLAIN` would abort the user's
+/// transaction.
+pub async fn describe_columns(
+    conn: &mut PgConnection,
+    query: &str,
+) -> Result<Vec<ColumnMetadata>, sqlx::Error> {
+    let statement = conn.prepare(query).await?;
+    let columns = statement.columns();
+
+    let table_columns: Vec<(Oid, i16)> = columns
+        .iter()
+        .filter_map(|col| Some((col.relation_id()?, col.relation_attribute_no()?)))
+        .collect();
+    let nullability = column_nullability(conn, &table_columns).await?;
+
+    Ok(columns
+        .iter()
+        .map(|col| {
+            let nullable = col
+                .relation_id()
+                .zip(col.relation_attribute_no())
+                .and_then(|key| nullability.get(&key).copied());
+            column_metadata(col.name(), col.type_info(), nullable)
+        })
+        .collect())
+}
+
+fn column_metadata(name: &str, type_info: &PgTypeInfo, nullable: Option<bool>) -> ColumnMetadata {
+    ColumnMetadata {
+        name: name.to_string(),
+        data_type: type_info.name().to_string(),
+        type_oid: type_info.oid().map(|oid| oid.0),
+        nullable,
+    }
+}
+
+/// Looks up `NOT NULL` constraints of table columns.
+///
+/// This is only a hint for the result set: an outer join can still produce
+/// NULLs in a `NOT NULL` column.
+async fn column_nullability(
+    conn: &mut PgConnection,
+    table_columns: &[(Oid, i16)],
+) -> Result<HashMap<(Oid, i16), bool>, sqlx::Error> {
+    if table_columns.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let (relids, attnums): (Vec<Oid>, Vec<i16>) = table_columns.iter().copied().unzip();
+    let rows: Vec<(Oid, i16, bool)> = sqlx::query_as(
+        r#"
+        SELECT a.attrelid, a.attnum, NOT a.attnotnull
+        FROM unnest($1::oid[], $2::int2[]) AS c(relid, attnum)
+        JOIN pg_catalog.pg_attribute a ON a.attrelid = c.relid AND a.attnum = c.attnum
+        "#,
+    )
+    .bind(relids)
+    .bind(attnums)
+    .fetch_all(conn)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(relid, attnum, nullable)| ((relid, attnum), nullable))
+        .collect())
+}
@@ -0,0 +1,290 @@
+//! Compilation of table data filters into parameterized SQL
+
+use crate::db::binding::{find_column, param_text, Params};
+use crate::db::sql_utils::safe_identifier;
+use crate::error::AppError;
+use crate::types::{FilterCombinator, FilterOperator, TableColumnInfo, TableFilter};
+use serde_json::Value;
+
+/// Compiles a filter into a boolean SQL expression.
+///
+/// Column names are checked against `columns` and quoted; values are added
+/// to `params` and cast to the column type, so they never appear in the SQL
+/// text.
+pub fn filter_clause(
+    filter: &TableFilter,
+    columns: &[TableColumnInfo],
+    params: &mut Params,
+) -> Result<String, AppError> {
+    match filter {
+        TableFilter::Condition {
+            column,
+            operator,
+            value,
+        } => condition_clause(find_column(columns, column)?, *operator, value, params),
+        TableFilter::Group {
+            combinator,
+            filters,
+        } => {
+            let (separator, empty) = match combinator {
+                FilterCombinator::And => (" AND ", "TRUE"),
+                FilterCombinator::Or => (" OR ", "FALSE"),
+            };
+            if filters.is_empty() {
+                return Ok(empty.to_string());
+            }
+
+            let clauses = filters
+                .iter()
+                .map(|f| filter_clause(f, columns, params))
+                .collect::<Result<Vec<_>, _>>()?;
+            Ok(format!("({})", clauses.join(separator)))
+        }
+    }
+}
+
+fn condition_clause(
+    column: &TableColumnInfo,
+    operator: FilterOperator,
+    value: &Value,
+    params: &mut Params,
+) -> Result<String, AppError> {
+    let name = safe_identifier(&column.name)?;
+
+    let clause = match operator {
+        FilterOperator::Eq | FilterOperator::NotEq | FilterOperator::Lt | FilterOperator::Gt => {
+            let op = match operator {
+                FilterOperator::Eq => "=",
+                FilterOperator::NotEq => "<>",
+                FilterOperator::Lt => "<",
+                _ => ">",
+            };
+            let placeholder = push_value(column, value, params)?;
+            format!("{} {} {}", name, op, placeholder)
+        }
+        FilterOperator::Like | FilterOperator::ILike => {
+            let op = if operator == FilterOperator::Like {
+                "LIKE"
+            } else {
+                "ILIKE"
+            };
+            // Compare as text so patterns also work on numbers, dates, ...
+            let pattern = match value {
+                Value::String(s) => s.clone(),
+                Value::Number(_) | Value::Bool(_) => value.to_string(),
+                _ => return Err(invalid(column, "expects a text pattern")),
+            };
+            format!("{}::text {} {}", name, op, params.push_text(pattern))
+        }
+        FilterOperator::In => {
+            let Value::Array(items) = value else {
+                return Err(invalid(column, "`in` expects an array of values"));
+            };
+            if items.is_empty() {
+                return Ok("FALSE".to_string());
+            }
+            let placeholders = items
+                .iter()
+                .map(|item| push_value(column, item, params))
+                .collect::<Result<Vec<_>, _>>()?;
+            format!("{} IN ({})", name, placeholders.join(", "))
+        }
+        FilterOperator::IsNull => format!("{} IS NULL", name),
+        FilterOperator::Between => {
+            let bounds = match value {
+                Value::Array(items) if items.len() == 2 => items,
+                _ => return Err(invalid(column, "`between` expects [low, high]")),
+            };
+            let low = push_value(column, &bounds[0], params)?;
+            let high = push_value(column, &bounds[1], params)?;
+            format!("{} BETWEEN {} AND {}", name, low, high)
+        }
+    };
+
+    Ok(clause)
+}
+
+/// Adds a comparison value; NULL never compares equal, so it's rejected in
+/// favor of `is_null`.
+fn push_value(
+    column: &TableColumnInfo,
+    value: &Value,
+    params: &mut Params,
+) -> Result<String, AppError> {
+    match param_text(column, value)? {
+        Some(text) => Ok(params.push(column, Some(text))),
+        None => Err(invalid(column, "cannot compare with NULL; use `is_null`")),
+    }
+}
+
+fn invalid(column: &TableColumnInfo, message: &str) -> AppError {
+    AppError::InvalidFilter(format!("column \"{}\" {}", column.name, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(name: &str, sql_type: &str) -> TableColumnInfo {
+        TableColumnInfo {
+            name: name.to_string(),
+            data_type: sql_type.to_string(),
+            sql_type: sql_type.to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_auto_generated: false,
+            default_value: None,
+        }
+    }
+
+    fn condition(column: &str, operator: FilterOperator, value: Value) -> TableFilter {
+        TableFilter::Condition {
+            column: column.to_string(),
+            operator,
+            value,
+        }
+    }
+
+    fn columns() -> Vec<TableColumnInfo> {
+        vec![
+            column("id", "integer"),
+            column("name", "text"),
+            column("created", "date"),
+        ]
+    }
+
+    #[test]
+    fn test_condition_clauses() {
+        let cols = columns();
+        let mut params = Params::default();
+
+        let sql = filter_clause(
+            &condition("id", FilterOperator::Gt, json!(10)),
+            &cols,
+            &mut params,
+        )
+        .unwrap();
+        assert_eq!(sql, "\"id\" > $1::integer");
+
+        let sql = filter_clause(
+            &condition("id", FilterOperator::ILike, json!("1%")),
+            &cols,
+            &mut params,
+        )
+        .unwrap();
+        assert_eq!(sql, "\"id\"::text ILIKE $2::text");
+
+        let sql = filter_clause(
+            &condition("id", FilterOperator::In, json!([1, 2])),
+            &cols,
+            &mut params,
+        )
+        .unwrap();
+        assert_eq!(sql, "\"id\" IN ($3::integer, $4::integer)");
+
+        let sql = filter_clause(
+            &condition(
+                "created",
+                FilterOperator::Between,
+                json!(["2024-01-01", "2024-12-31"]),
+            ),
+            &cols,
+            &mut params,
+        )
+        .unwrap();
+        assert_eq!(sql, "\"created\" BETWEEN $5::date AND $6::date");
+
+        let sql = filter_clause(
+            &condition("name", FilterOperator::IsNull, Value::Null),
+            &cols,
+            &mut params,
+        )
+        .unwrap();
+        assert_eq!(sql, "\"name\" IS NULL");
+
+        let values: Vec<_> = params.values().iter().map(|v| v.as_deref()).collect();
+        assert_eq!(
+            values,
+            vec![
+                Some("10"),
+                Some("1%"),
+                Some("1"),
+                Some("2"),
+                Some("2024-01-01"),
+                Some("2024-12-31")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_from_json() {
+        let filter: TableFilter = serde_json::from_value(json!({
+            "kind": "condition",
+            "column": "name",
+            "operator": "ilike",
+            "value": "a%"
+        }))
+        .unwrap();
+        assert!(matches!(
+            filter,
+            TableFilter::Condition {
+                operator: FilterOperator::ILike,
+                ..
+            }
+        ));
+        assert_eq!(
+            serde_json::to_value(FilterOperator::NotEq).unwrap(),
+            json!("not_eq")
+        );
+    }
+
+    #[test]
+    fn test_nested_groups() {
+        let filter = TableFilter::Group {
+            combinator: FilterCombinator::And,
+            filters: vec![
+                condition("name", FilterOperator::Eq, json!("x'); DROP TABLE t; --")),
+                TableFilter::Group {
+                    combinator: FilterCombinator::Or,
+                    filters: vec![
+                        condition("id", FilterOperator::Lt, json!(5)),
+                        condition("id", FilterOperator::NotEq, json!(7)),
+                    ],
+                },
+            ],
+        };
+
+        let mut params = Params::default();
+        let sql = filter_clause(&filter, &columns(), &mut params).unwrap();
+        assert_eq!(
+            sql,
+            "(\"name\" = $1::text AND (\"id\" < $2::integer OR \"id\" <> $3::integer))"
+        );
+        assert_eq!(params.values()[0].as_deref(), Some("x'); DROP TABLE t; --"));
+    }
+
+    #[test]
+    fn test_invalid_filters() {
+        let cols = columns();
+        let mut params = Params::default();
+
+        for filter in [
+            condition("missing", FilterOperator::Eq, json!(1)),
+            condition("id", FilterOperator::Eq, Value::Null),
+            condition("id", FilterOperator::In, json!(1)),
+            condition("id", FilterOperator::Between, json!([1])),
+        ] {
+            assert!(filter_clause(&filter, &cols, &mut params).is_err());
+        }
+
+        let empty_or = TableFilter::Group {
+            combinator: FilterCombinator::Or,
+            filters: vec![],
+        };
+        assert_eq!(
+            filter_clause(&empty_or, &cols, &mut params).unwrap(),
+            "FALSE"
+        );
+    }
+}
@@ -1,9 +1,22 @@
+pub mod binding;
 mod connection;
+pub mod count;
+pub mod cursor;
+pub mod describe;
+pub mod filter;
 pub mod queries;
+pub mod row_key;
 pub mod row_utils;
+pub mod schema_cache;
+pub mod script;
+mod session;
+pub mod sort;
 pub mod sql_utils;
+mod ssh_tunnel;
+pub mod undo;
 
 pub use connection::DatabaseConnection;
+pub use session::Session;
 
 // Re-export types for convenience
 pub use crate::types::ConnectionConfig;
@@ -4,38 +4,120 @@ pub const GET_SCHEMAS: &str = r#"
 SELECT schema_name
 FROM information_schema.schemata
 WHERE schema_name NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
+  AND schema_name NOT LIKE 'pg\_temp\_%'
+  AND schema_name NOT LIKE 'pg\_toast\_temp\_%'
 ORDER BY schema_name
 "#;
 
+/// Relations of the schemas in `$1` that can be browsed, with the parent
+/// of each partition. Lists what `information_schema.tables` would, plus
+/// materialized views.
 pub const GET_TABLES: &str = r#"
-SELECT table_name
-FROM information_schema.tables
-WHERE table_schema = $1
-  AND table_type = 'BASE TABLE'
-ORDER BY table_name
+SELECT
+    n.nspname::text as table_schema,
+    c.relname::text as table_name,
+    c.relkind::text as relkind,
+    pn.nspname::text as parent_schema,
+    p.relname::text as parent_table
+FROM pg_catalog.pg_class c
+JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+LEFT JOIN pg_catalog.pg_inherits i ON i.inhrelid = c.oid AND c.relispartition
+LEFT JOIN pg_catalog.pg_class p ON p.oid = i.inhparent
+LEFT JOIN pg_catalog.pg_namespace pn ON pn.oid = p.relnamespace
+WHERE n.nspname = ANY($1)
+  AND c.relkind IN ('r', 'p', 'v', 'm', 'f')
+  AND (pg_has_role(c.relowner, 'USAGE')
+    OR has_table_privilege(c.oid, 'SELECT, INSERT, UPDATE, DELETE, TRUNCATE, REFERENCES, TRIGGER')
+    OR has_any_column_privilege(c.oid, 'SELECT, INSERT, UPDATE, REFERENCES'))
+ORDER BY n.nspname, c.relname
 "#;
 
-pub const GET_COLUMNS: &str = r#"
+/// Builds a query listing the columns of the relations matching a
+/// condition, ordered by schema, relation and position.
+///
+/// `information_schema.columns` leaves out materialized views, so this
+/// reads the catalog directly and computes `data_type` and `is_nullable`
+/// the way `information_schema` does.
+macro_rules! columns_query {
+    ($condition:literal) => {
+        concat!(
+            r#"
 SELECT
-    c.column_name,
-    c.data_type,
-    c.is_nullable,
-    c.column_default,
-    CASE WHEN pk.column_name IS NOT NULL THEN true ELSE false END as is_primary_key
-FROM information_schema.columns c
-LEFT JOIN (
-    SELECT kcu.column_name
-    FROM information_schema.table_constraints tc
-    JOIN information_schema.key_column_usage kcu
-        ON tc.constraint_name = kcu.constraint_name
-        AND tc.table_schema = kcu.table_schema
-    WHERE tc.constraint_type = 'PRIMARY KEY'
-        AND tc.table_schema = $1
-        AND tc.table_name = $2
-) pk ON c.column_name = pk.column_name
-WHERE c.table_schema = $1
-  AND c.table_name = $2
-ORDER BY c.ordinal_position
+    n.nspname::text as table_schema,
+    c.relname::text as table_name,
+    a.attname::text as column_name,
+    CASE
+        WHEN t.typtype = 'd' THEN
+            CASE
+                WHEN bt.typelem <> 0 AND bt.typlen = -1 THEN 'ARRAY'
+                WHEN bn.nspname = 'pg_catalog' THEN format_type(t.typbasetype, NULL)
+                ELSE 'USER-DEFINED'
+            END
+        WHEN t.typelem <> 0 AND t.typlen = -1 THEN 'ARRAY'
+        WHEN tn.nspname = 'pg_catalog' THEN format_type(a.atttypid, NULL)
+        ELSE 'USER-DEFINED'
+    END as data_type,
+    CASE a.atttypid
+        -- `character` and `bit` without a modifier mean length 1. Cast to
+        -- the unconstrained types instead, so assigning a too-long value is
+        -- an error rather than truncated. `bit[]` has no `=` with
+        -- `varbit[]`, so it keeps its modifier
+        WHEN 'bpchar'::regtype::oid THEN 'bpchar'
+        WHEN '_bpchar'::regtype::oid THEN 'bpchar[]'
+        WHEN 'bit'::regtype::oid THEN 'varbit'
+        WHEN '_bit'::regtype::oid THEN format_type(a.atttypid, a.atttypmod)
+        ELSE format_type(a.atttypid, NULL)
+    END as sql_type,
+    CASE WHEN a.attnotnull OR (t.typtype = 'd' AND t.typnotnull) THEN 'NO' ELSE 'YES' END
+        as is_nullable,
+    CASE WHEN a.attgenerated = '' THEN pg_get_expr(d.adbin, d.adrelid) END as column_default,
+    COALESCE(a.attnum = ANY(pk.indkey), false) as is_primary_key,
+    (COALESCE(pg_get_expr(d.adbin, d.adrelid) LIKE 'nextval%', false)
+        OR a.attgenerated <> ''
+        OR a.attidentity <> '') as is_auto_generated
+FROM pg_catalog.pg_attribute a
+JOIN pg_catalog.pg_class c ON c.oid = a.attrelid
+JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+JOIN pg_catalog.pg_type t ON t.oid = a.atttypid
+JOIN pg_catalog.pg_namespace tn ON tn.oid = t.typnamespace
+LEFT JOIN pg_catalog.pg_type bt ON t.typtype = 'd' AND bt.oid = t.typbasetype
+LEFT JOIN pg_catalog.pg_namespace bn ON bn.oid = bt.typnamespace
+LEFT JOIN pg_catalog.pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+LEFT JOIN pg_catalog.pg_index pk ON pk.indrelid = a.attrelid AND pk.indisprimary
+WHERE a.attnum > 0
+  AND NOT a.attisdropped
+  AND "#,
+            $condition,
+            r#"
+ORDER BY n.nspname, c.relname, a.attnum
+"#
+        )
+    };
+}
+
+/// Columns of one relation of any kind
+pub const GET_COLUMNS: &str =
+    columns_query!("a.attrelid = to_regclass(format('%I.%I', $1::text, $2::text))");
+
+/// Columns of every relation in the schemas in `$1` that `GET_TABLES` lists
+pub const GET_SCHEMA_COLUMNS: &str =
+    columns_query!("n.nspname = ANY($1) AND c.relkind IN ('r', 'p', 'v', 'm', 'f')");
+
+/// Kind of a relation, as a `pg_class.relkind` code
+pub const GET_RELKIND: &str = r#"
+SELECT relkind::text
+FROM pg_catalog.pg_class
+WHERE oid = to_regclass(format('%I.%I', $1::text, $2::text))
+"#;
+
+/// The `SELECT` defining a view or materialized view
+pub const GET_VIEW_DEFINITION: &str = r#"
+SELECT pg_get_viewdef(c.oid, true)
+FROM pg_catalog.pg_class c
+JOIN pg_catalog.pg_namespace n ON n.oid = c.relnamespace
+WHERE n.nspname = $1
+  AND c.relname = $2
+  AND c.relkind IN ('v', 'm')
 "#;
 
 pub const GET_FOREIGN_KEYS: &str = r#"
@@ -116,3 +198,57 @@ WHERE tc.table_schema = $1
 GROUP BY tc.constraint_name, tc.constraint_type, cc.check_clause
 ORDER BY tc.constraint_type, tc.constraint_name
 "#;
+
+/// A value that changes whenever a schema, or a relation, column, default
+/// or constraint in one, is created, dropped or altered. Only the schemas
+/// and relations this connection lists are looked at, so other sessions'
+/// temporary tables and TOAST tables don't move it. New relations raise the
+/// largest OID, dropped ones lower the count, and renamed or moved ones
+/// change the digest of names; rewriting a table's storage changes none of
+/// these. Other catalog rows are tracked by the sum of their row versions
+/// (`xmin`), which unlike the newest version moves whatever the transaction
+/// ids are after wraparound; freezing rows moves it too, which only causes
+/// a reload.
+pub const GET_CATALOG_FINGERPRINT: &str = r#"
+WITH schemas AS (
+    SELECT oid, xmin
+    FROM pg_catalog.pg_namespace
+    WHERE nspname NOT IN ('pg_catalog', 'information_schema', 'pg_toast')
+      AND nspname NOT LIKE 'pg\_temp\_%'
+      AND nspname NOT LIKE 'pg\_toast\_temp\_%'
+      AND (pg_has_role(nspowner, 'USAGE') OR has_schema_privilege(oid, 'CREATE, USAGE'))
+),
+relations AS (
+    SELECT c.oid, c.relnamespace, c.relname
+    FROM pg_catalog.pg_class c
+    JOIN schemas s ON s.oid = c.relnamespace
+    WHERE c.relkind IN ('r', 'p', 'v', 'm', 'f')
+)
+SELECT concat_ws(':', n.count, n.xmins, c.count, c.max_oid, c.names,
+                 a.count, a.xmins, d.count, d.xmins, k.count, k.xmins)
+FROM (
+    SELECT count(*), coalesce(sum(xmin::text::bigint), 0)
+    FROM schemas
+) n(count, xmins),
+(
+    SELECT count(*), coalesce(max(oid::bigint), 0),
+           coalesce(md5(string_agg(concat_ws(' ', oid, relnamespace, relname), ','
+                                   ORDER BY oid)), '')
+    FROM relations
+) c(count, max_oid, names),
+(
+    SELECT count(*), coalesce(sum(a.xmin::text::bigint), 0)
+    FROM relations r
+    JOIN pg_catalog.pg_attribute a ON a.attrelid = r.oid AND a.attnum > 0
+) a(count, xmins),
+(
+    SELECT count(*), coalesce(sum(d.xmin::text::bigint), 0)
+    FROM relations r
+    JOIN pg_catalog.pg_attrdef d ON d.adrelid = r.oid
+) d(count, xmins),
+(
+    SELECT count(*), coalesce(sum(k.xmin::text::bigint), 0)
+    FROM relations r
+    JOIN pg_catalog.pg_constraint k ON k.conrelid = r.oid
+) k(count, xmins)
+"#;
@@ -0,0 +1,349 @@
+//! Identification of table rows for editing
+//!
+//! Rows are identified by the primary key when there is one, otherwise by
+//! the narrowest unique index over non-null columns. Plain tables with
+//! neither fall back to the row's physical location (`tableoid`, `ctid`),
+//! guarded by a match on every column so that a location reused by another
+//! row is never mistaken for the original one.
+
+use crate::db::binding::{find_column, param_text, Params};
+use crate::db::queries;
+use crate::db::row_utils::{get_column_value, ValueFormat};
+use crate::db::sql_utils::safe_identifier;
+use crate::error::AppError;
+use crate::types::{RelationKind, RowKeyKind, TableColumnInfo};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::postgres::PgRow;
+use sqlx::{Column, PgPool, Row};
+
+/// How the rows of one table are identified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowKey {
+    pub kind: RowKeyKind,
+    /// Key columns; every column of the table for `Ctid`
+    pub columns: Vec<String>,
+}
+
+/// A row id as sent to the client, tagged with the strategy that made it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RowId {
+    kind: RowKeyKind,
+    values: Map<String, Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tableoid: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ctid: Option<String>,
+}
+
+/// Unique, non-partial indexes on plain columns that are all `NOT NULL`,
+/// narrowest first
+const UNIQUE_INDEX_QUERY: &str = r#"
+    SELECT array_agg(a.attname::text ORDER BY k.ord) AS columns
+    FROM pg_catalog.pg_index i
+    CROSS JOIN LATERAL unnest(i.indkey) WITH ORDINALITY AS k(attnum, ord)
+    JOIN pg_catalog.pg_attribute a
+        ON a.attrelid = i.indrelid AND a.attnum = k.attnum
+    WHERE i.indrelid = format('%I.%I', $1::text, $2::text)::regclass
+      AND i.indisunique
+      AND i.indisvalid
+      AND i.indpred IS NULL
+      AND i.indexprs IS NULL
+      AND k.ord <= i.indnkeyatts
+    GROUP BY i.indexrelid
+    HAVING bool_and(a.attnotnull)
+    ORDER BY count(*), i.indexrelid
+    LIMIT 1
+"#;
+
+/// Looks up the kind of a relation; errors if there's no such relation or
+/// it can't be browsed.
+pub async fn relation_kind(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+) -> Result<RelationKind, AppError> {
+    let relkind: Option<String> = sqlx::query_scalar(queries::GET_RELKIND)
+        .bind(schema)
+        .bind(table)
+        .fetch_optional(pool)
+        .await?;
+
+    relkind
+        .as_deref()
+        .and_then(RelationKind::from_relkind)
+        .ok_or_else(|| {
+            AppError::InvalidConfig(format!(
+                "\"{}\".\"{}\" is not a table or view",
+                schema, table
+            ))
+        })
+}
+
+/// Picks how rows of a relation are identified, or `None` if they can't be
+/// (views, materialized views, foreign tables, ...).
+pub async fn row_key(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+    kind: RelationKind,
+    columns: &[TableColumnInfo],
+) -> Result<Option<RowKey>, AppError> {
+    // Materialized views can have unique indexes, but rows of anything but
+    // a table can't be written
+    if !kind.is_writable() {
+        return Ok(None);
+    }
+
+    let primary_keys: Vec<String> = columns
+        .iter()
+        .filter(|c| c.is_primary_key)
+        .map(|c| c.name.clone())
+        .collect();
+    if !primary_keys.is_empty() {
+        return Ok(Some(RowKey {
+            kind: RowKeyKind::PrimaryKey,
+            columns: primary_keys,
+        }));
+    }
+
+    let unique: Option<Vec<String>> = sqlx::query_scalar(UNIQUE_INDEX_QUERY)
+        .bind(schema)
+        .bind(table)
+        .fetch_optional(pool)
+        .await?;
+    if let Some(unique) = unique {
+        return Ok(Some(RowKey {
+            kind: RowKeyKind::UniqueIndex,
+            columns: unique,
+        }));
+    }
+
+    // Ordinary and partitioned tables have a meaningful ctid
+    Ok(Some(RowKey {
+        kind: RowKeyKind::Ctid,
+        columns: columns.iter().map(|c| c.name.clone()).collect(),
+    }))
+}
+
+impl RowKey {
+    /// Whether the key is made of columns that can break ties in a sort
+    /// and be sought from a cursor.
+    pub fn is_sortable(&self) -> bool {
+        self.kind != RowKeyKind::Ctid
+    }
+
+    /// Select list returning the table's columns followed by what's needed
+    /// to identify and version each row.
+    pub fn select_list(&self) -> &'static str {
+        match self.kind {
+            RowKeyKind::PrimaryKey | RowKeyKind::UniqueIndex => "*, xmin::text AS xmin",
+            RowKeyKind::Ctid => {
+                "*, xmin::text AS xmin, tableoid::text AS tableoid, ctid::text AS ctid"
+            }
+        }
+    }
+
+    /// Builds the id of a row selected with [`select_list`](Self::select_list).
+    pub fn row_id(&self, row: &PgRow, format: ValueFormat) -> String {
+        let mut values = Map::new();
+        for name in &self.columns {
+            if let Some(idx) = row.columns().iter().position(|c| c.name() == name) {
+                values.insert(name.clone(), get_column_value(row, idx, format));
+            }
+        }
+
+        let (tableoid, ctid) = match self.kind {
+            RowKeyKind::Ctid => (row.try_get("tableoid").ok(), row.try_get("ctid").ok()),
+            _ => (None, None),
+        };
+
+        let id = RowId {
+            kind: self.kind,
+            values,
+            tableoid,
+            ctid,
+        };
+        serde_json::to_string(&id).unwrap_or_default()
+    }
+
+    /// Compiles the condition matching the row with id `row_id`.
+    pub fn where_clause(
+        &self,
+        row_id: &str,
+        columns: &[TableColumnInfo],
+        params: &mut Params,
+    ) -> Result<String, AppError> {
+        let id = parse_row_id(row_id)?;
+        if id.kind != self.kind {
+            return Err(AppError::InvalidConfig(
+                "Row id was made for a different key; reload the table".to_string(),
+            ));
+        }
+
+        let mut conditions = Vec::new();
+        if self.kind == RowKeyKind::Ctid {
+            let (Some(tableoid), Some(ctid)) = (id.tableoid, id.ctid) else {
+                return Err(AppError::InvalidConfig(format!(
+                    "Invalid row id: {}",
+                    row_id
+                )));
+            };
+            conditions.push(format!("tableoid = {}::oid", params.push_text(tableoid)));
+            conditions.push(format!("ctid = {}::tid", params.push_text(ctid)));
+        }
+
+        for name in &self.columns {
+            let value = id.values.get(name).ok_or_else(|| {
+                AppError::InvalidConfig(format!("Row id is missing key column {}", name))
+            })?;
+            let column = find_column(columns, name)?;
+            conditions.push(match self.kind {
+                RowKeyKind::Ctid => value_matches(column, value, params)?,
+                _ => format!(
+                    "{} = {}",
+                    safe_identifier(&column.name)?,
+                    params.push(column, param_text(column, value)?)
+                ),
+            });
+        }
+
+        Ok(conditions.join(" AND "))
+    }
+
+    /// Predicts the id of row `row_id` once `column` is set to `value`.
+    ///
+    /// An updated row also moves to a new `ctid`, which is only known once
+    /// the update has run, so ids by `ctid` keep the one they were made with.
+    pub fn updated_id(
+        &self,
+        row_id: &str,
+        column: &str,
+        value: &Value,
+    ) -> Result<String, AppError> {
+        let mut id = parse_row_id(row_id)?;
+        if let Some(key_value) = id.values.get_mut(column) {
+            *key_value = value.clone();
+        }
+        Ok(serde_json::to_string(&id).unwrap_or_default())
+    }
+}
+
+fn parse_row_id(row_id: &str) -> Result<RowId, AppError> {
+    serde_json::from_str(row_id)
+        .map_err(|_| AppError::InvalidConfig(format!("Invalid row id: {}", row_id)))
+}
+
+/// Compiles a condition that holds while `column` still has `value` as it
+/// was loaded.
+///
+/// Values are compared through the column type's text output, so equal
+/// values match even when the type has no `=` operator or the client
+/// formatted them differently; `json` is compared as `jsonb` to ignore
+/// whitespace.
+pub fn value_matches(
+    column: &TableColumnInfo,
+    value: &Value,
+    params: &mut Params,
+) -> Result<String, AppError> {
+    let name = safe_identifier(&column.name)?;
+    let condition = match param_text(column, value)? {
+        None => format!("{} IS NULL", name),
+        Some(text) => {
+            let placeholder = params.push(column, Some(text));
+            if matches!(column.sql_type.as_str(), "json" | "jsonb") {
+                format!("{}::jsonb = {}::jsonb", name, placeholder)
+            } else {
+                format!("{}::text = ({})::text", name, placeholder)
+            }
+        }
+    };
+    Ok(condition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(name: &str, sql_type: &str) -> TableColumnInfo {
+        TableColumnInfo {
+            name: name.to_string(),
+            data_type: sql_type.to_string(),
+            sql_type: sql_type.to_string(),
+            is_nullable: true,
+            is_primary_key: false,
+            is_auto_generated: false,
+            default_value: None,
+        }
+    }
+
+    #[test]
+    fn test_key_where_clause() {
+        let columns = vec![column("a", "integer"), column("b", "text")];
+        let key = RowKey {
+            kind: RowKeyKind::UniqueIndex,
+            columns: vec!["b".to_string(), "a".to_string()],
+        };
+
+        let mut params = Params::default();
+        let sql = key
+            .where_clause(
+                r#"{"kind":"unique_index","values":{"a":1,"b":"x"}}"#,
+                &columns,
+                &mut params,
+            )
+            .unwrap();
+        assert_eq!(sql, "\"b\" = $1::text AND \"a\" = $2::integer");
+
+        // Ids made under another strategy are rejected
+        let mut params = Params::default();
+        assert!(key
+            .where_clause(
+                r#"{"kind":"primary_key","values":{"a":1,"b":"x"}}"#,
+                &columns,
+                &mut params
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_updated_id() {
+        let key = RowKey {
+            kind: RowKeyKind::PrimaryKey,
+            columns: vec!["id".to_string()],
+        };
+        let id = r#"{"kind":"primary_key","values":{"id":1}}"#;
+        assert_eq!(
+            key.updated_id(id, "id", &json!("2")).unwrap(),
+            r#"{"kind":"primary_key","values":{"id":"2"}}"#
+        );
+        assert_eq!(key.updated_id(id, "name", &json!("x")).unwrap(), id);
+        assert!(key.updated_id("1", "id", &json!(2)).is_err());
+    }
+
+    #[test]
+    fn test_ctid_where_clause() {
+        let columns = vec![column("a", "integer"), column("doc", "json")];
+        let key = RowKey {
+            kind: RowKeyKind::Ctid,
+            columns: vec!["a".to_string(), "doc".to_string()],
+        };
+        let id = json!({
+            "kind": "ctid",
+            "values": {"a": null, "doc": {"k": 1}},
+            "tableoid": "16384",
+            "ctid": "(0,3)"
+        });
+
+        let mut params = Params::default();
+        let sql = key
+            .where_clause(&id.to_string(), &columns, &mut params)
+            .unwrap();
+        assert_eq!(
+            sql,
+            "tableoid = $1::text::oid AND ctid = $2::text::tid AND \"a\" IS NULL \
+             AND \"doc\"::jsonb = $3::json::jsonb"
+        );
+    }
+}
@@ -1,64 +1,1181 @@
-use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
-use sqlx::postgres::PgRow;
-use sqlx::{Column, Row};
-
-/// Extracts a typed value from a PostgreSQL row column and converts it to JSON.
-pub fn get_column_value(row: &PgRow, idx: usize, type_name: &str) -> serde_json::Value {
-    match type_name {
-        "INT2" | "INT4" | "INT8" => row
-            .try_get::<i64, _>(idx)
-            .map(serde_json::Value::from)
-            .unwrap_or(serde_json::Value::Null),
-        "FLOAT4" | "FLOAT8" | "NUMERIC" => row
-            .try_get::<f64, _>(idx)
-            .map(serde_json::Value::from)
-            .unwrap_or(serde_json::Value::Null),
-        "BOOL" => row
-            .try_get::<bool, _>(idx)
-            .map(serde_json::Value::from)
-            .unwrap_or(serde_json::Value::Null),
-        "TEXT" | "VARCHAR" | "CHAR" | "NAME" => row
-            .try_get::<String, _>(idx)
-            .map(serde_json::Value::from)
-            .unwrap_or(serde_json::Value::Null),
-        "TIMESTAMPTZ" => row
-            .try_get::<DateTime<Utc>, _>(idx)
-            .map(|dt| serde_json::Value::String(dt.to_rfc3339()))
-            .unwrap_or(serde_json::Value::Null),
-        "TIMESTAMP" => row
-            .try_get::<NaiveDateTime, _>(idx)
-            .map(|dt| serde_json::Value::String(dt.to_string()))
-            .unwrap_or(serde_json::Value::Null),
-        "DATE" => row
-            .try_get::<NaiveDate, _>(idx)
-            .map(|d| serde_json::Value::String(d.to_string()))
-            .unwrap_or(serde_json::Value::Null),
-        "TIME" => row
-            .try_get::<NaiveTime, _>(idx)
-            .map(|t| serde_json::Value::String(t.to_string()))
-            .unwrap_or(serde_json::Value::Null),
-        "UUID" => row
-            .try_get::<uuid::Uuid, _>(idx)
-            .map(|u| serde_json::Value::String(u.to_string()))
-            .unwrap_or(serde_json::Value::Null),
-        "JSON" | "JSONB" => row
-            .try_get::<serde_json::Value, _>(idx)
-            .unwrap_or(serde_json::Value::Null),
-        _ => row
-            .try_get::<String, _>(idx)
-            .map(serde_json::Value::from)
-            .unwrap_or(serde_json::Value::Null),
+use serde_json::Value;
+use sqlx::postgres::{PgRow, PgTypeInfo, PgTypeKind, PgValueFormat, PgValueRef};
+use sqlx::{Executor, Postgres, Row, TypeInfo, ValueRef};
+use std::fmt::Write;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Extracts a value from a PostgreSQL row column and converts it to JSON.
+///
+/// Values are decoded from their wire representation so that nothing is
+/// lost: exact numerics (`NUMERIC`, `MONEY`) become strings, arrays become
+/// JSON arrays, composites become objects, and types without a natural JSON
+/// form (intervals, network addresses, ranges, geometry, ...) use their
+/// PostgreSQL text representation. `BYTEA` is rendered in PostgreSQL's hex
+/// format (`\x0a1b`).
+pub fn get_column_value(row: &PgRow, idx: usize, format: ValueFormat) -> Value {
+    match row.try_get_raw(idx) {
+        Ok(value) => decode_value(&value, format),
+        Err(_) => Value::Null,
     }
 }
 
 /// Converts all columns of a PostgreSQL row to a vector of JSON values.
-pub fn row_to_values(row: &PgRow) -> Vec<serde_json::Value> {
-    row.columns()
-        .iter()
-        .enumerate()
-        .map(|(i, col)| {
-            let type_name = col.type_info().to_string();
-            get_column_value(row, i, &type_name)
-        })
+pub fn row_to_values(row: &PgRow, format: ValueFormat) -> Vec<Value> {
+    (0..row.len())
+        .map(|i| get_column_value(row, i, format))
         .collect()
 }
+
+/// Session settings that decoding depends on.
+///
+/// `MONEY` is sent as a count of the currency's smallest unit; how many of
+/// its digits are fractional depends on the session's `lc_monetary`.
+#[derive(Debug, Clone, Copy)]
+pub struct ValueFormat {
+    money_scale: u32,
+}
+
+impl ValueFormat {
+    /// Reads the settings of the session `executor` runs on.
+    pub async fn load<'e, E>(executor: E) -> Result<Self, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        let money_scale: i32 = sqlx::query_scalar("SELECT scale('1'::money::numeric)")
+            .fetch_one(executor)
+            .await?;
+
+        Ok(Self {
+            money_scale: money_scale.unsigned_abs(),
+        })
+    }
+
+    /// Reads the settings only if values of these types may need them:
+    /// `MONEY`, its arrays, and records and user-defined types, which may
+    /// contain it.
+    pub async fn load_for<'e, E>(
+        executor: E,
+        type_oids: impl IntoIterator<Item = Option<u32>>,
+    ) -> Result<Self, sqlx::Error>
+    where
+        E: Executor<'e, Database = Postgres>,
+    {
+        const FIRST_NORMAL_OBJECT_ID: u32 = 16384;
+        let needed = type_oids.into_iter().any(|oid| match oid {
+            // money, money[], record, record[]
+            Some(790 | 791 | 2249 | 2287) => true,
+            Some(oid) => oid >= FIRST_NORMAL_OBJECT_ID,
+            None => true,
+        });
+
+        if needed {
+            Self::load(executor).await
+        } else {
+            Ok(Self::default())
+        }
+    }
+}
+
+impl Default for ValueFormat {
+    /// The format of the default `C` locale
+    fn default() -> Self {
+        Self { money_scale: 2 }
+    }
+}
+
+fn decode_value(value: &PgValueRef<'_>, format: ValueFormat) -> Value {
+    if value.is_null() {
+        return Value::Null;
+    }
+
+    let type_info = value.type_info();
+    match value.format() {
+        PgValueFormat::Binary => match value.as_bytes() {
+            Ok(bytes) => decode_binary(bytes, oid_of(&type_info), Some(&type_info), format),
+            Err(_) => Value::Null,
+        },
+        PgValueFormat::Text => match value.as_str() {
+            Ok(text) => decode_text(text, &type_info),
+            Err(_) => Value::Null,
+        },
+    }
+}
+
+fn oid_of(type_info: &PgTypeInfo) -> u32 {
+    type_info.oid().map(|oid| oid.0).unwrap_or(0)
+}
+
+/// Values only arrive as text from the simple query protocol; keep the
+/// text unless the type has a direct JSON equivalent.
+fn decode_text(text: &str, type_info: &PgTypeInfo) -> Value {
+    match type_info.name() {
+        "BOOL" => Value::Bool(text == "t"),
+        "INT2" | "INT4" | "INT8" | "OID" => text
+            .parse::<i64>()
+            .map(Value::from)
+            .unwrap_or_else(|_| Value::String(text.to_string())),
+        "FLOAT4" | "FLOAT8" => float_value(text.parse::<f64>().unwrap_or(f64::NAN)),
+        "JSON" | "JSONB" => {
+            serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+        }
+        _ => Value::String(text.to_string()),
+    }
+}
+
+/// Decodes a value in PostgreSQL's binary format.
+///
+/// `type_info` is the type as resolved by sqlx and is needed for enums,
+/// domains, composites and user-defined arrays and ranges. Fields of
+/// anonymous records only carry an OID, so they are decoded by OID alone.
+fn decode_binary(
+    bytes: &[u8],
+    oid: u32,
+    type_info: Option<&PgTypeInfo>,
+    format: ValueFormat,
+) -> Value {
+    if let Some(type_info) = type_info {
+        match type_info.kind() {
+            PgTypeKind::Domain(base) => {
+                return decode_binary(bytes, oid_of(base), Some(base), format)
+            }
+            PgTypeKind::Enum(_) => return text_or_hex(bytes),
+            PgTypeKind::Array(elem) => {
+                return decode_array(bytes, Some(elem), format).unwrap_or_else(|| hex(bytes))
+            }
+            PgTypeKind::Range(elem) => {
+                return decode_range(bytes, oid_of(elem), Some(elem), format)
+                    .map(Value::String)
+                    .unwrap_or_else(|| hex(bytes))
+            }
+            PgTypeKind::Composite(fields) => {
+                return decode_record(bytes, Some(fields), format).unwrap_or_else(|| hex(bytes))
+            }
+            PgTypeKind::Simple | PgTypeKind::Pseudo => {}
+        }
+    }
+
+    decode_builtin(bytes, oid, format).unwrap_or_else(|| text_or_hex(bytes))
+}
+
+fn decode_builtin(bytes: &[u8], oid: u32, format: ValueFormat) -> Option<Value> {
+    let mut buf = Reader::new(bytes);
+    let value = match oid {
+        // bool
+        16 => Value::Bool(buf.u8()? != 0),
+        // bytea
+        17 => hex(bytes),
+        // "char"
+        18 => Value::String(char::from(buf.u8()?).to_string()),
+        // int8
+        20 => Value::from(buf.i64()?),
+        // int2
+        21 => Value::from(buf.i16()?),
+        // int4
+        23 => Value::from(buf.i32()?),
+        // oid, xid, cid and the reg* aliases of oid
+        24 | 26 | 28 | 29 | 2202..=2206 | 3734 | 3769 | 4089 | 4096 | 4191 => {
+            Value::from(buf.u32()?)
+        }
+        // tid
+        27 => Value::String(format!("({},{})", buf.u32()?, buf.u16()?)),
+        // json
+        114 => serde_json::from_slice(bytes).unwrap_or_else(|_| text_or_hex(bytes)),
+        // point
+        600 => Value::String(point(&mut buf)?),
+        // lseg
+        601 => Value::String(format!("[{},{}]", point(&mut buf)?, point(&mut buf)?)),
+        // path
+        602 => {
+            let closed = buf.u8()? != 0;
+            let points = points(&mut buf)?;
+            Value::String(if closed {
+                format!("({})", points)
+            } else {
+                format!("[{}]", points)
+            })
+        }
+        // box
+        603 => Value::String(format!("{},{}", point(&mut buf)?, point(&mut buf)?)),
+        // polygon
+        604 => Value::String(format!("({})", points(&mut buf)?)),
+        // line
+        628 => Value::String(format!("{{{},{},{}}}", buf.f64()?, buf.f64()?, buf.f64()?)),
+        // cidr, inet
+        650 | 869 => Value::String(inet(&mut buf, oid == 650)?),
+        // float4; formatted through its shortest representation so 0.1
+        // stays 0.1 instead of 0.10000000149011612
+        700 => float_value(f32::from_be_bytes(buf.array()?).to_string().parse().ok()?),
+        // float8
+        701 => float_value(buf.f64()?),
+        // circle
+        718 => Value::String(format!("<{},{}>", point(&mut buf)?, buf.f64()?)),
+        // macaddr8, macaddr
+        774 | 829 => Value::String(
+            bytes
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(":"),
+        ),
+        // money, in the smallest currency unit
+        790 => Value::String(scaled_integer(buf.i64()?, format.money_scale)),
+        // date
+        1082 => Value::String(date(buf.i32()?)),
+        // time
+        1083 => Value::String(time_of_day(buf.i64()?)),
+        // timestamp
+        1114 => Value::String(timestamp(buf.i64()?)),
+        // timestamptz
+        1184 => Value::String(timestamptz(buf.i64()?)),
+        // interval
+        1186 => {
+            let micros = buf.i64()?;
+            let days = buf.i32()?;
+            let months = buf.i32()?;
+            Value::String(interval(months, days, micros))
+        }
+        // timetz
+        1266 => {
+            let micros = buf.i64()?;
+            let zone = buf.i32()?;
+            Value::String(format!("{}{}", time_of_day(micros), utc_offset(-zone)))
+        }
+        // bit, varbit
+        1560 | 1562 => Value::String(bit_string(&mut buf)?),
+        // numeric
+        1700 => Value::String(numeric(&mut buf)?),
+        // void
+        2278 => Value::Null,
+        // record
+        2249 => decode_record(bytes, None, format)?,
+        // uuid
+        2950 => Value::String(uuid::Uuid::from_slice(bytes).ok()?.to_string()),
+        // txid_snapshot, pg_snapshot
+        2970 | 5038 => Value::String(snapshot(&mut buf)?),
+        // pg_lsn
+        3220 => {
+            let lsn = buf.u64()?;
+            Value::String(format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF))
+        }
+        // tsvector
+        3614 => Value::String(tsvector(&mut buf)?),
+        // tsquery
+        3615 => Value::String(tsquery(&mut buf)?),
+        // jsonb, jsonpath: a version byte followed by text
+        3802 => serde_json::from_slice(bytes.get(1..)?).ok()?,
+        4072 => text_or_hex(bytes.get(1..)?),
+        // built-in ranges decoded by OID, e.g. inside anonymous records
+        3904 | 3906 | 3908 | 3910 | 3912 | 3926 => {
+            Value::String(decode_range(bytes, range_element(oid)?, None, format)?)
+        }
+        // multiranges
+        4451 | 4532..=4536 => Value::String(multirange(&mut buf, oid, format)?),
+        // xid8
+        5069 => Value::from(buf.u64()?),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// Element type OID of a built-in range or multirange type.
+fn range_element(oid: u32) -> Option<u32> {
+    Some(match oid {
+        3904 | 4451 => 23,
+        3906 | 4532 => 1700,
+        3908 | 4533 => 1114,
+        3910 | 4534 => 1184,
+        3912 | 4535 => 1082,
+        3926 | 4536 => 20,
+        _ => return None,
+    })
+}
+
+fn text_or_hex(bytes: &[u8]) -> Value {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => Value::String(text.to_string()),
+        Err(_) => hex(bytes),
+    }
+}
+
+/// Formats bytes like PostgreSQL's `bytea_output = hex`.
+fn hex(bytes: &[u8]) -> Value {
+    let mut out = String::with_capacity(2 + bytes.len() * 2);
+    out.push_str("\\x");
+    for b in bytes {
+        let _ = write!(out, "{:02x}", b);
+    }
+    Value::String(out)
+}
+
+/// JSON has no NaN or infinities, so those are spelled as in PostgreSQL.
+fn float_value(v: f64) -> Value {
+    if v.is_nan() {
+        Value::String("NaN".to_string())
+    } else if v.is_infinite() {
+        Value::String(if v > 0.0 { "Infinity" } else { "-Infinity" }.to_string())
+    } else {
+        Value::from(v)
+    }
+}
+
+fn decode_array(bytes: &[u8], elem: Option<&PgTypeInfo>, format: ValueFormat) -> Option<Value> {
+    let mut buf = Reader::new(bytes);
+    let ndim = usize::try_from(buf.i32()?).ok()?;
+    let _has_nulls = buf.i32()?;
+    let elem_oid = buf.u32()?;
+
+    let mut dims = Vec::with_capacity(ndim);
+    for _ in 0..ndim {
+        dims.push(usize::try_from(buf.i32()?).ok()?);
+        let _lower_bound = buf.i32()?;
+    }
+    if dims.is_empty() {
+        return Some(Value::Array(Vec::new()));
+    }
+
+    array_dimension(&mut buf, &dims, elem_oid, elem, format)
+}
+
+fn array_dimension(
+    buf: &mut Reader<'_>,
+    dims: &[usize],
+    elem_oid: u32,
+    elem: Option<&PgTypeInfo>,
+    format: ValueFormat,
+) -> Option<Value> {
+    let (len, inner) = dims.split_first()?;
+    let mut items = Vec::with_capacity(*len);
+    for _ in 0..*len {
+        let item = if inner.is_empty() {
+            match buf.value()? {
+                Some(bytes) => decode_binary(bytes, elem_oid, elem, format),
+                None => Value::Null,
+            }
+        } else {
+            array_dimension(buf, inner, elem_oid, elem, format)?
+        };
+        items.push(item);
+    }
+    Some(Value::Array(items))
+}
+
+/// Decodes a composite value into an object keyed by field name, or into an
+/// array for anonymous records.
+fn decode_record(
+    bytes: &[u8],
+    fields: Option<&[(String, PgTypeInfo)]>,
+    format: ValueFormat,
+) -> Option<Value> {
+    let mut buf = Reader::new(bytes);
+    let count = usize::try_from(buf.i32()?).ok()?;
+
+    let mut values = Vec::with_capacity(count);
+    for i in 0..count {
+        let oid = buf.u32()?;
+        let field_type = fields.and_then(|fields| fields.get(i)).map(|(_, ty)| ty);
+        values.push(match buf.value()? {
+            Some(bytes) => decode_binary(bytes, oid, field_type, format),
+            None => Value::Null,
+        });
+    }
+
+    match fields {
+        Some(fields) if fields.len() == values.len() => Some(Value::Object(
+            fields
+                .iter()
+                .map(|(name, _)| name.clone())
+                .zip(values)
+                .collect(),
+        )),
+        _ => Some(Value::Array(values)),
+    }
+}
+
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LB_INC: u8 = 0x02;
+const RANGE_UB_INC: u8 = 0x04;
+const RANGE_LB_INF: u8 = 0x08;
+const RANGE_UB_INF: u8 = 0x10;
+
+/// Formats a range like PostgreSQL, e.g. `[1,10)` or `empty`.
+fn decode_range(
+    bytes: &[u8],
+    elem_oid: u32,
+    elem: Option<&PgTypeInfo>,
+    format: ValueFormat,
+) -> Option<String> {
+    let mut buf = Reader::new(bytes);
+    let flags = buf.u8()?;
+    if flags & RANGE_EMPTY != 0 {
+        return Some("empty".to_string());
+    }
+
+    let mut bound = |infinite: bool| -> Option<String> {
+        if infinite {
+            return Some(String::new());
+        }
+        let bytes = buf.value()??;
+        Some(range_bound(&decode_binary(bytes, elem_oid, elem, format)))
+    };
+    let lower = bound(flags & RANGE_LB_INF != 0)?;
+    let upper = bound(flags & RANGE_UB_INF != 0)?;
+
+    Some(format!(
+        "{}{},{}{}",
+        if flags & RANGE_LB_INC != 0 { '[' } else { '(' },
+        lower,
+        upper,
+        if flags & RANGE_UB_INC != 0 { ']' } else { ')' },
+    ))
+}
+
+fn range_bound(value: &Value) -> String {
+    match value {
+        Value::String(s) => {
+            let needs_quotes = s.is_empty()
+                || s.chars()
+                    .any(|c| c.is_whitespace() || "\"\\,()[]".contains(c));
+            if needs_quotes {
+                format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+            } else {
+                s.clone()
+            }
+        }
+        other => other.to_string(),
+    }
+}
+
+fn multirange(buf: &mut Reader<'_>, oid: u32, format: ValueFormat) -> Option<String> {
+    let elem_oid = range_element(oid)?;
+    let count = usize::try_from(buf.i32()?).ok()?;
+    let mut ranges = Vec::with_capacity(count);
+    for _ in 0..count {
+        ranges.push(decode_range(buf.value()??, elem_oid, None, format)?);
+    }
+    Some(format!("{{{}}}", ranges.join(",")))
+}
+
+/// Formats NUMERIC's base-10000 digits exactly, honoring the display scale.
+fn numeric(buf: &mut Reader<'_>) -> Option<String> {
+    const NEGATIVE: u16 = 0x4000;
+    const NAN: u16 = 0xC000;
+    const POS_INF: u16 = 0xD000;
+    const NEG_INF: u16 = 0xF000;
+
+    let ndigits = usize::try_from(buf.i16()?).ok()?;
+    let weight = i32::from(buf.i16()?);
+    let sign = buf.u16()?;
+    let dscale = usize::from(buf.u16()?);
+    let mut digits = Vec::with_capacity(ndigits);
+    for _ in 0..ndigits {
+        digits.push(buf.i16()?);
+    }
+
+    match sign {
+        NAN => return Some("NaN".to_string()),
+        POS_INF => return Some("Infinity".to_string()),
+        NEG_INF => return Some("-Infinity".to_string()),
+        _ => {}
+    }
+
+    // Digit `i` has weight `weight - i` in base 10000
+    let digit = |w: i32| -> i16 {
+        usize::try_from(weight - w)
+            .ok()
+            .and_then(|i| digits.get(i).copied())
+            .unwrap_or(0)
+    };
+
+    let mut out = String::new();
+    if sign == NEGATIVE {
+        out.push('-');
+    }
+    if weight < 0 {
+        out.push('0');
+    } else {
+        let _ = write!(out, "{}", digit(weight));
+        for w in (0..weight).rev() {
+            let _ = write!(out, "{:04}", digit(w));
+        }
+    }
+
+    if dscale > 0 {
+        let mut fraction = String::with_capacity(dscale + 4);
+        let mut w = -1;
+        while fraction.len() < dscale {
+            let _ = write!(fraction, "{:04}", digit(w));
+            w -= 1;
+        }
+        fraction.truncate(dscale);
+        out.push('.');
+        out.push_str(&fraction);
+    }
+
+    Some(out)
+}
+
+fn scaled_integer(value: i64, scale: u32) -> String {
+    if scale == 0 {
+        return value.to_string();
+    }
+    let factor = 10i128.pow(scale);
+    let value = i128::from(value);
+    let sign = if value < 0 { "-" } else { "" };
+    let abs = value.abs();
+    format!(
+        "{}{}.{:0width$}",
+        sign,
+        abs / factor,
+        abs % factor,
+        width = scale as usize
+    )
+}
+
+const MICROS_PER_DAY: i64 = 86_400_000_000;
+
+fn date(days: i32) -> String {
+    match days {
+        i32::MAX => "infinity".to_string(),
+        i32::MIN => "-infinity".to_string(),
+        _ => {
+            let (year, month, day) = civil_date(i64::from(days));
+            with_era(year, |year| format!("{:04}-{:02}-{:02}", year, month, day))
+        }
+    }
+}
+
+fn timestamp(micros: i64) -> String {
+    match micros {
+        i64::MAX => "infinity".to_string(),
+        i64::MIN => "-infinity".to_string(),
+        _ => {
+            let (year, month, day) = civil_date(micros.div_euclid(MICROS_PER_DAY));
+            let time = clock(micros.rem_euclid(MICROS_PER_DAY).unsigned_abs());
+            with_era(year, |year| {
+                format!("{:04}-{:02}-{:02} {}", year, month, day, time)
+            })
+        }
+    }
+}
+
+/// Formats a `timestamptz` in UTC as RFC 3339; years before 1 AD are
+/// astronomical (0 is 1 BC).
+fn timestamptz(micros: i64) -> String {
+    match micros {
+        i64::MAX => "infinity".to_string(),
+        i64::MIN => "-infinity".to_string(),
+        _ => {
+            let (year, month, day) = civil_date(micros.div_euclid(MICROS_PER_DAY));
+            let time = clock(micros.rem_euclid(MICROS_PER_DAY).unsigned_abs());
+            let sign = if year < 0 { "-" } else { "" };
+            format!(
+                "{}{:04}-{:02}-{:02}T{}+00:00",
+                sign,
+                year.abs(),
+                month,
+                day,
+                time
+            )
+        }
+    }
+}
+
+/// Converts days since 2000-01-01, PostgreSQL's epoch, into a proleptic
+/// Gregorian year (0 being 1 BC), month and day.
+///
+/// Computed directly rather than with chrono, whose dates end long before
+/// PostgreSQL's in year 5874897.
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    // Days since 0000-03-01, so leap days fall at the end of each year
+    let days = days + 730_425;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + i64::from(month <= 2);
+
+    (year, month as u32, day as u32)
+}
+
+/// PostgreSQL has no year zero and writes years before 1 AD with a `BC`
+/// suffix.
+fn with_era(year: i64, format: impl FnOnce(i64) -> String) -> String {
+    if year > 0 {
+        format(year)
+    } else {
+        format!("{} BC", format(1 - year))
+    }
+}
+
+/// Formats microseconds since midnight as `HH:MM:SS[.ffffff]`.
+fn time_of_day(micros: i64) -> String {
+    let mut out = clock(micros.unsigned_abs());
+    if micros < 0 {
+        out.insert(0, '-');
+    }
+    out
+}
+
+fn clock(micros: u64) -> String {
+    let secs = micros / 1_000_000;
+    let frac = micros % 1_000_000;
+    let mut out = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if frac > 0 {
+        let frac = format!("{:06}", frac);
+        out.push('.');
+        out.push_str(frac.trim_end_matches('0'));
+    }
+    out
+}
+
+/// Formats an offset east of UTC in seconds as `+HH[:MM[:SS]]`.
+fn utc_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let abs = offset.unsigned_abs();
+    let mut out = format!("{}{:02}", sign, abs / 3600);
+    if !abs.is_multiple_of(3600) {
+        let _ = write!(out, ":{:02}", abs / 60 % 60);
+        if !abs.is_multiple_of(60) {
+            let _ = write!(out, ":{:02}", abs % 60);
+        }
+    }
+    out
+}
+
+/// Formats an interval in PostgreSQL's default `postgres` style, e.g.
+/// `1 year 2 mons 3 days 04:05:06.5`.
+fn interval(months: i32, days: i32, micros: i64) -> String {
+    let mut parts: Vec<String> = Vec::new();
+    let mut any_negative = false;
+
+    let mut push_unit = |value: i64, unit: &str, parts: &mut Vec<String>| {
+        if value == 0 {
+            return;
+        }
+        let sign = if value > 0 && any_negative { "+" } else { "" };
+        let plural = if value == 1 { "" } else { "s" };
+        parts.push(format!("{}{} {}{}", sign, value, unit, plural));
+        any_negative |= value < 0;
+    };
+    push_unit(i64::from(months / 12), "year", &mut parts);
+    push_unit(i64::from(months % 12), "mon", &mut parts);
+    push_unit(i64::from(days), "day", &mut parts);
+
+    if micros != 0 || parts.is_empty() {
+        let sign = if micros < 0 {
+            "-"
+        } else if any_negative {
+            "+"
+        } else {
+            ""
+        };
+        parts.push(format!("{}{}", sign, clock(micros.unsigned_abs())));
+    }
+
+    parts.join(" ")
+}
+
+fn point(buf: &mut Reader<'_>) -> Option<String> {
+    Some(format!("({},{})", buf.f64()?, buf.f64()?))
+}
+
+fn points(buf: &mut Reader<'_>) -> Option<String> {
+    let count = usize::try_from(buf.i32()?).ok()?;
+    let points = (0..count).map(|_| point(buf)).collect::<Option<Vec<_>>>()?;
+    Some(points.join(","))
+}
+
+fn inet(buf: &mut Reader<'_>, is_cidr: bool) -> Option<String> {
+    const AF_INET: u8 = 2;
+
+    let family = buf.u8()?;
+    let bits = buf.u8()?;
+    let _is_cidr = buf.u8()?;
+    let len = usize::from(buf.u8()?);
+    let addr = buf.take(len)?;
+
+    let (addr, max_bits) = if family == AF_INET {
+        let octets: [u8; 4] = addr.try_into().ok()?;
+        (Ipv4Addr::from(octets).to_string(), 32)
+    } else {
+        let octets: [u8; 16] = addr.try_into().ok()?;
+        (Ipv6Addr::from(octets).to_string(), 128)
+    };
+
+    // inet omits the netmask of a single host
+    if is_cidr || bits != max_bits {
+        Some(format!("{}/{}", addr, bits))
+    } else {
+        Some(addr)
+    }
+}
+
+fn bit_string(buf: &mut Reader<'_>) -> Option<String> {
+    let len = usize::try_from(buf.i32()?).ok()?;
+    let bytes = buf.take(len.div_ceil(8))?;
+    Some(
+        (0..len)
+            .map(|i| {
+                if bytes[i / 8] & (0x80 >> (i % 8)) != 0 {
+                    '1'
+                } else {
+                    '0'
+                }
+            })
+            .collect(),
+    )
+}
+
+fn snapshot(buf: &mut Reader<'_>) -> Option<String> {
+    let count = usize::try_from(buf.i32()?).ok()?;
+    let xmin = buf.u64()?;
+    let xmax = buf.u64()?;
+    let xips = (0..count)
+        .map(|_| buf.u64().map(|xip| xip.to_string()))
+        .collect::<Option<Vec<_>>>()?;
+    Some(format!("{}:{}:{}", xmin, xmax, xips.join(",")))
+}
+
+/// Quotes a text search lexeme as PostgreSQL prints it.
+fn lexeme(text: &str) -> String {
+    format!("'{}'", text.replace('\\', "\\\\").replace('\'', "''"))
+}
+
+fn tsvector(buf: &mut Reader<'_>) -> Option<String> {
+    let count = usize::try_from(buf.i32()?).ok()?;
+    let mut entries = Vec::with_capacity(count);
+    for _ in 0..count {
+        let mut entry = lexeme(buf.cstr()?);
+        let positions = (0..buf.u16()?)
+            .map(|_| {
+                let pos = buf.u16()?;
+                let weight = match pos >> 14 {
+                    3 => "A",
+                    2 => "B",
+                    1 => "C",
+                    _ => "",
+                };
+                Some(format!("{}{}", pos & 0x3FFF, weight))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if !positions.is_empty() {
+            entry.push(':');
+            entry.push_str(&positions.join(","));
+        }
+        entries.push(entry);
+    }
+    Some(entries.join(" "))
+}
+
+fn tsquery(buf: &mut Reader<'_>) -> Option<String> {
+    let count = usize::try_from(buf.i32()?).ok()?;
+    if count == 0 {
+        return Some(String::new());
+    }
+    tsquery_item(buf, true)
+}
+
+/// Items are stored in prefix order: an operator, then its right operand,
+/// then its left operand.
+fn tsquery_item(buf: &mut Reader<'_>, top_level: bool) -> Option<String> {
+    const QI_VAL: u8 = 1;
+    const OP_NOT: u8 = 1;
+    const OP_AND: u8 = 2;
+    const OP_OR: u8 = 3;
+
+    if buf.u8()? == QI_VAL {
+        let weight = buf.u8()?;
+        let prefix = buf.u8()? != 0;
+        let mut out = lexeme(buf.cstr()?);
+        let weights: String = [(8, 'A'), (4, 'B'), (2, 'C'), (1, 'D')]
+            .iter()
+            .filter(|(bit, _)| weight & bit != 0)
+            .map(|(_, c)| *c)
+            .collect();
+        if prefix || !weights.is_empty() {
+            out.push(':');
+            if prefix {
+                out.push('*');
+            }
+            out.push_str(&weights);
+        }
+        return Some(out);
+    }
+
+    let op = buf.u8()?;
+    if op == OP_NOT {
+        return Some(format!("!{}", tsquery_item(buf, false)?));
+    }
+    let operator = match op {
+        OP_AND => " & ".to_string(),
+        OP_OR => " | ".to_string(),
+        _ => match buf.u16()? {
+            1 => " <-> ".to_string(),
+            distance => format!(" <{}> ", distance),
+        },
+    };
+    let right = tsquery_item(buf, false)?;
+    let left = tsquery_item(buf, false)?;
+    if top_level {
+        Some(format!("{}{}{}", left, operator, right))
+    } else {
+        Some(format!("( {}{}{} )", left, operator, right))
+    }
+}
+
+/// Cursor over a big-endian binary value.
+struct Reader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf }
+    }
+
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.buf.len() < n {
+            return None;
+        }
+        let (head, tail) = self.buf.split_at(n);
+        self.buf = tail;
+        Some(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.take(N)?.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        Some(i16::from_be_bytes(self.array()?))
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes(self.array()?))
+    }
+
+    fn i32(&mut self) -> Option<i32> {
+        Some(i32::from_be_bytes(self.array()?))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.array()?))
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        Some(i64::from_be_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_be_bytes(self.array()?))
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        Some(f64::from_be_bytes(self.array()?))
+    }
+
+    /// Reads a length-prefixed value; `None` inside is SQL NULL.
+    fn value(&mut self) -> Option<Option<&'a [u8]>> {
+        let len = self.i32()?;
+        if len < 0 {
+            return Some(None);
+        }
+        self.take(usize::try_from(len).ok()?).map(Some)
+    }
+
+    fn cstr(&mut self) -> Option<&'a str> {
+        let end = self.buf.iter().position(|&b| b == 0)?;
+        let text = std::str::from_utf8(&self.buf[..end]).ok()?;
+        self.buf = &self.buf[end + 1..];
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use sqlx::{Connection, PgConnection};
+
+    #[test]
+    fn test_numeric_formatting() {
+        // 12345.678 = digits [1, 2345, 6780], weight 1, dscale 3
+        let bytes = [0, 3, 0, 1, 0, 0, 0, 3, 0, 1, 0x09, 0x29, 0x1A, 0x7C];
+        assert_eq!(
+            numeric(&mut Reader::new(&bytes)).as_deref(),
+            Some("12345.678")
+        );
+
+        // -0.00012 = digits [1, 2000], weight -1, dscale 5
+        let bytes = [0, 2, 0xFF, 0xFF, 0x40, 0, 0, 5, 0, 1, 0x07, 0xD0];
+        assert_eq!(
+            numeric(&mut Reader::new(&bytes)).as_deref(),
+            Some("-0.00012")
+        );
+
+        // 1000000 = digits [100], weight 1, dscale 0
+        let bytes = [0, 1, 0, 1, 0, 0, 0, 0, 0, 100];
+        assert_eq!(
+            numeric(&mut Reader::new(&bytes)).as_deref(),
+            Some("1000000")
+        );
+    }
+
+    #[test]
+    fn test_interval_formatting() {
+        assert_eq!(
+            interval(14, 3, 14_706_500_000),
+            "1 year 2 mons 3 days 04:05:06.5"
+        );
+        assert_eq!(interval(0, 0, 0), "00:00:00");
+        assert_eq!(interval(0, 1, -3_600_000_000), "1 day -01:00:00");
+        assert_eq!(interval(0, -1, 3_600_000_000), "-1 days +01:00:00");
+        assert_eq!(interval(-12, 0, 0), "-1 years");
+    }
+
+    #[test]
+    fn test_scaled_integer() {
+        assert_eq!(scaled_integer(123456, 2), "1234.56");
+        assert_eq!(scaled_integer(-5, 2), "-0.05");
+        assert_eq!(scaled_integer(i64::MIN, 2), "-92233720368547758.08");
+        assert_eq!(scaled_integer(1234, 0), "1234");
+        assert_eq!(scaled_integer(-1234, 3), "-1.234");
+    }
+
+    #[test]
+    fn test_date_time_formatting() {
+        assert_eq!(date(0), "2000-01-01");
+        assert_eq!(date(-1), "1999-12-31");
+        assert_eq!(date(8825), "2024-02-29");
+        assert_eq!(date(-746_117), "0044-03-15 BC");
+        // The last date PostgreSQL accepts, far beyond chrono's range
+        assert_eq!(date(2_145_031_948), "5874897-12-31");
+        assert_eq!(date(-2_451_179), "4713-11-24 BC");
+
+        assert_eq!(timestamp(-1), "1999-12-31 23:59:59.999999");
+        assert_eq!(
+            timestamp(8825 * MICROS_PER_DAY + 3_723_500_000),
+            "2024-02-29 01:02:03.5"
+        );
+        assert_eq!(
+            timestamp(-746_117 * MICROS_PER_DAY),
+            "0044-03-15 00:00:00 BC"
+        );
+        assert_eq!(
+            timestamp(9_223_371_331_199_999_999),
+            "294276-12-31 23:59:59.999999"
+        );
+        assert_eq!(timestamptz(0), "2000-01-01T00:00:00+00:00");
+        assert_eq!(
+            timestamptz(-746_117 * MICROS_PER_DAY),
+            "-0043-03-15T00:00:00+00:00"
+        );
+        // Values beyond PostgreSQL's range still format without panicking
+        assert_eq!(date(i32::MAX - 1), "5881610-07-10");
+        assert_eq!(timestamp(i64::MAX - 1), "294277-01-09 04:00:54.775806");
+        assert_eq!(
+            timestamptz(i64::MIN + 1),
+            "-290278-12-22T19:59:05.224193+00:00"
+        );
+    }
+
+    #[test]
+    fn test_range_bound_quoting() {
+        assert_eq!(range_bound(&json!(5)), "5");
+        assert_eq!(
+            range_bound(&json!("2024-01-01 00:00:00")),
+            "\"2024-01-01 00:00:00\""
+        );
+        assert_eq!(range_bound(&json!("2024-01-01")), "2024-01-01");
+    }
+
+    fn test_database_url() -> String {
+        std::env::var("TABLEX_TEST_DATABASE_URL")
+            .unwrap_or_else(|_| "postgres://postgres@localhost:5432/postgres".to_string())
+    }
+
+    async fn select_value(conn: &mut PgConnection, expr: &str) -> Value {
+        let row = sqlx::query(&format!("SELECT {}", expr))
+            .fetch_one(&mut *conn)
+            .await
+            .unwrap_or_else(|e| panic!("SELECT {} failed: {}", expr, e));
+        let format = ValueFormat::load(&mut *conn).await.unwrap();
+        get_column_value(&row, 0, format)
+    }
+
+    /// Run with `cargo test -- --ignored` and `TABLEX_TEST_DATABASE_URL`
+    /// pointing at a scratch database.
+    #[tokio::test]
+    #[ignore = "requires a local PostgreSQL"]
+    async fn test_decode_type_matrix() {
+        let mut conn = PgConnection::connect(&test_database_url()).await.unwrap();
+        let mut tx = conn.begin().await.unwrap();
+        sqlx::query("CREATE TYPE mood AS ENUM ('sad', 'happy')")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        sqlx::query("CREATE TYPE pair AS (a int4, b text)")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        sqlx::query("CREATE DOMAIN positive AS int4 CHECK (VALUE > 0)")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+        sqlx::query("SET LOCAL TIME ZONE 'UTC'")
+            .execute(&mut *tx)
+            .await
+            .unwrap();
+
+        let cases: Vec<(&str, Value)> = vec![
+            // Numbers
+            ("NULL::int4", Value::Null),
+            ("true", json!(true)),
+            ("32767::int2", json!(32767)),
+            ("(-2147483648)::int4", json!(-2147483648i64)),
+            ("9223372036854775807::int8", json!(9223372036854775807i64)),
+            ("0.1::float4", json!(0.1)),
+            ("1.5::float8", json!(1.5)),
+            ("'NaN'::float8", json!("NaN")),
+            ("'-Infinity'::float4", json!("-Infinity")),
+            (
+                "123456789012345678901234567890.123456789::numeric",
+                json!("123456789012345678901234567890.123456789"),
+            ),
+            ("0.00012::numeric", json!("0.00012")),
+            ("(-42.500)::numeric", json!("-42.500")),
+            ("1e20::numeric", json!("100000000000000000000")),
+            ("'NaN'::numeric", json!("NaN")),
+            ("12.34::money", json!("12.34")),
+            ("(-0.05)::money", json!("-0.05")),
+            ("42::oid", json!(42)),
+            ("'pg_class'::regclass", json!(1259)),
+            // Text
+            ("'héllo'::text", json!("héllo")),
+            ("'ab'::char(4)", json!("ab  ")),
+            ("'x'::\"char\"", json!("x")),
+            ("'name'::name", json!("name")),
+            ("'<a>b</a>'::xml", json!("<a>b</a>")),
+            ("'happy'::mood", json!("happy")),
+            ("'\\xdeadbeef'::bytea", json!("\\xdeadbeef")),
+            // Date and time
+            ("'2024-02-29'::date", json!("2024-02-29")),
+            ("'infinity'::date", json!("infinity")),
+            ("'0044-03-15 BC'::date", json!("0044-03-15 BC")),
+            ("'5874897-12-31'::date", json!("5874897-12-31")),
+            ("'12:34:56.789'::time", json!("12:34:56.789")),
+            ("'04:05:06+02:30'::timetz", json!("04:05:06+02:30")),
+            (
+                "'2024-01-02 03:04:05.123456'::timestamp",
+                json!("2024-01-02 03:04:05.123456"),
+            ),
+            ("'-infinity'::timestamp", json!("-infinity")),
+            (
+                "'294276-12-31 23:59:59.999999'::timestamp",
+                json!("294276-12-31 23:59:59.999999"),
+            ),
+            (
+                "'2024-01-02 03:04:05+00'::timestamptz",
+                json!("2024-01-02T03:04:05+00:00"),
+            ),
+            (
+                "'1 year 2 mons 3 days 04:05:06.5'::interval",
+                json!("1 year 2 mons 3 days 04:05:06.5"),
+            ),
+            ("'-1 day +1 hour'::interval", json!("-1 days +01:00:00")),
+            // Identifiers and JSON
+            (
+                "'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'::uuid",
+                json!("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"),
+            ),
+            ("'{\"a\": [1, 2]}'::json", json!({"a": [1, 2]})),
+            ("'{\"a\": null}'::jsonb", json!({"a": null})),
+            ("'$.a[*]'::jsonpath", json!("$.\"a\"[*]")),
+            // Network
+            ("'192.168.0.1'::inet", json!("192.168.0.1")),
+            ("'192.168.0.1/24'::inet", json!("192.168.0.1/24")),
+            ("'10.0.0.0/8'::cidr", json!("10.0.0.0/8")),
+            ("'2001:db8::1/64'::inet", json!("2001:db8::1/64")),
+            ("'08:00:2b:01:02:03'::macaddr", json!("08:00:2b:01:02:03")),
+            (
+                "'08:00:2b:01:02:03:04:05'::macaddr8",
+                json!("08:00:2b:01:02:03:04:05"),
+            ),
+            // Bit strings
+            ("B'101'::bit(3)", json!("101")),
+            ("B'1011001011'::varbit", json!("1011001011")),
+            // Arrays
+            ("'{1,2,NULL}'::int4[]", json!([1, 2, null])),
+            ("'{{1,2},{3,4}}'::int8[]", json!([[1, 2], [3, 4]])),
+            ("'{}'::text[]", json!([])),
+            ("'{1.10,2}'::numeric[]", json!(["1.10", "2"])),
+            ("ARRAY['sad', 'happy']::mood[]", json!(["sad", "happy"])),
+            ("'{\"a b\",c}'::varchar[]", json!(["a b", "c"])),
+            // Ranges
+            ("'[1,10)'::int4range", json!("[1,10)")),
+            ("'empty'::int8range", json!("empty")),
+            ("'(,5]'::numrange", json!("(,5]")),
+            (
+                "'[2024-01-01,2024-02-01)'::daterange",
+                json!("[2024-01-01,2024-02-01)"),
+            ),
+            (
+                "'[2024-01-01 00:00,2024-01-02 00:00)'::tsrange",
+                json!("[\"2024-01-01 00:00:00\",\"2024-01-02 00:00:00\")"),
+            ),
+            ("'{[1,3),[5,7)}'::int4multirange", json!("{[1,3),[5,7)}")),
+            // Composites and domains
+            ("ROW(1, 'x')::pair", json!({"a": 1, "b": "x"})),
+            ("ROW(1, 'x')", json!([1, "x"])),
+            ("5::positive", json!(5)),
+            // Geometry
+            ("'(1,2.5)'::point", json!("(1,2.5)")),
+            ("'[(0,0),(1,1)]'::lseg", json!("[(0,0),(1,1)]")),
+            ("'((0,0),(1,1))'::box", json!("(1,1),(0,0)")),
+            ("'[(0,0),(1,1)]'::path", json!("[(0,0),(1,1)]")),
+            (
+                "'((0,0),(1,1),(1,0))'::polygon",
+                json!("((0,0),(1,1),(1,0))"),
+            ),
+            ("'{1,-1,0}'::line", json!("{1,-1,0}")),
+            ("'<(0,0),2>'::circle", json!("<(0,0),2>")),
+            // Miscellaneous
+            ("'16/B374D848'::pg_lsn", json!("16/B374D848")),
+            ("'(0,1)'::tid", json!("(0,1)")),
+            ("'10:20:10,14,15'::pg_snapshot", json!("10:20:10,14,15")),
+            (
+                "'a:1A fat:2,4 cat'::tsvector",
+                json!("'a':1A 'cat' 'fat':2,4"),
+            ),
+            (
+                "'fat & (rat | !cat)'::tsquery",
+                json!("'fat' & ( 'rat' | !'cat' )"),
+            ),
+            ("'supern:*A'::tsquery", json!("'supern':*A")),
+        ];
+
+        let mut failures = Vec::new();
+        for (expr, expected) in cases {
+            let actual = select_value(&mut tx, expr).await;
+            if actual != expected {
+                failures.push(format!("{}: expected {}, got {}", expr, expected, actual));
+            }
+        }
+
+        tx.rollback().await.unwrap();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
@@ -0,0 +1,227 @@
+//! Per-connection cache of schema metadata
+//!
+//! Listing the tables and columns of a large database takes seconds, so
+//! results are kept per connection and reused while the catalog is
+//! unchanged. Lookups read a fingerprint of the catalog rows the metadata
+//! is built from, at most once every [`FINGERPRINT_TTL`], and drop
+//! everything cached if it differs. Changes made by other sessions may
+//! therefore take that long to show. Schema changes run from the query
+//! editor clear the cache straight away.
+
+use crate::db::queries;
+use crate::error::AppError;
+use crate::types::{ColumnInfo, ForeignKeyInfo, SchemaInfo, TableDetailInfo, TableInfo};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// How long a fingerprint is trusted before it's read again, so that the
+/// lookups of one refresh of the schema tree read it once
+const FINGERPRINT_TTL: Duration = Duration::from_secs(2);
+
+/// Schema metadata of one connection, as of one catalog fingerprint.
+#[derive(Debug, Default)]
+pub struct SchemaCache {
+    fingerprint: Option<String>,
+    /// When the fingerprint was last read
+    checked_at: Option<Instant>,
+    pub schema_names: Option<Vec<String>>,
+    /// Tables of each schema, without their columns
+    pub tables: HashMap<String, Vec<TableInfo>>,
+    /// Columns keyed by schema and table
+    pub columns: HashMap<(String, String), Vec<ColumnInfo>>,
+    /// Foreign keys of each schema
+    pub foreign_keys: HashMap<String, Vec<ForeignKeyInfo>>,
+    /// Table details keyed by schema and table
+    pub table_details: HashMap<(String, String), TableDetailInfo>,
+}
+
+impl SchemaCache {
+    /// Starts over if the catalog changed since the cache was filled.
+    fn validate(&mut self, fingerprint: &str, now: Instant) {
+        if self.fingerprint.as_deref() != Some(fingerprint) {
+            *self = Self {
+                fingerprint: Some(fingerprint.to_string()),
+                ..Self::default()
+            };
+        }
+        self.checked_at = Some(now);
+    }
+
+    /// The fingerprint, if it was read less than [`FINGERPRINT_TTL`] ago.
+    fn fresh_fingerprint(&self, now: Instant) -> Option<String> {
+        let checked_at = self.checked_at?;
+        if now.saturating_duration_since(checked_at) < FINGERPRINT_TTL {
+            self.fingerprint.clone()
+        } else {
+            None
+        }
+    }
+
+    /// A schema with its tables and their columns, if all of them are
+    /// cached.
+    pub fn schema(&self, name: &str) -> Option<SchemaInfo> {
+        let tables = self
+            .tables
+            .get(name)?
+            .iter()
+            .map(|table| {
+                let key = (table.schema.clone(), table.name.clone());
+                let columns = self.columns.get(&key)?.clone();
+                Some(TableInfo {
+                    columns,
+                    ..table.clone()
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(SchemaInfo {
+            name: name.to_string(),
+            tables,
+        })
+    }
+
+    /// Caches a schema with its tables and their columns.
+    pub fn put_schema(&mut self, schema: SchemaInfo) {
+        let mut tables = schema.tables;
+        for table in &mut tables {
+            let key = (table.schema.clone(), table.name.clone());
+            self.columns.insert(key, std::mem::take(&mut table.columns));
+        }
+        self.tables.insert(schema.name, tables);
+    }
+}
+
+/// Schema caches keyed by connection id
+pub type SchemaCaches = Mutex<HashMap<String, SchemaCache>>;
+
+/// Reads the catalog fingerprint; see [`queries::GET_CATALOG_FINGERPRINT`].
+pub async fn catalog_fingerprint(pool: &PgPool) -> Result<String, AppError> {
+    let fingerprint = sqlx::query_scalar(queries::GET_CATALOG_FINGERPRINT)
+        .fetch_one(pool)
+        .await?;
+    Ok(fingerprint)
+}
+
+/// Looks metadata up in a connection's cache, or loads and caches it.
+///
+/// The cache isn't locked while loading. What's loaded is only cached if
+/// the cache wasn't cleared meanwhile, so a load racing with a schema
+/// change never puts stale metadata back.
+pub async fn cached<T: Clone>(
+    caches: &SchemaCaches,
+    connection_id: &str,
+    pool: &PgPool,
+    lookup: impl Fn(&SchemaCache) -> Option<T>,
+    load: impl Future<Output = Result<T, AppError>>,
+    store: impl FnOnce(&mut SchemaCache, T),
+) -> Result<T, AppError> {
+    let fresh = caches
+        .lock()
+        .await
+        .get(connection_id)
+        .and_then(|cache| cache.fresh_fingerprint(Instant::now()));
+    let fingerprint = match fresh {
+        Some(fingerprint) => fingerprint,
+        None => {
+            let fingerprint = catalog_fingerprint(pool).await?;
+            let mut caches = caches.lock().await;
+            let cache = caches.entry(connection_id.to_string()).or_default();
+            cache.validate(&fingerprint, Instant::now());
+            fingerprint
+        }
+    };
+
+    {
+        let caches = caches.lock().await;
+        let cache = caches
+            .get(connection_id)
+            .filter(|cache| cache.fingerprint.as_deref() == Some(fingerprint.as_str()));
+        if let Some(value) = cache.and_then(&lookup) {
+            return Ok(value);
+        }
+    }
+
+    let value = load.await?;
+
+    let mut caches = caches.lock().await;
+    if let Some(cache) = caches.get_mut(connection_id) {
+        if cache.fingerprint.as_deref() == Some(fingerprint.as_str()) {
+            store(cache, value.clone());
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RelationKind;
+
+    fn table(schema: &str, name: &str, columns: &[&str]) -> TableInfo {
+        TableInfo {
+            schema: schema.to_string(),
+            name: name.to_string(),
+            kind: RelationKind::Table,
+            parent_schema: None,
+            parent_table: None,
+            columns: columns
+                .iter()
+                .map(|name| ColumnInfo {
+                    name: name.to_string(),
+                    data_type: "integer".to_string(),
+                    is_nullable: true,
+                    is_primary_key: false,
+                    default_value: None,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_schema_cache() {
+        let now = Instant::now();
+        let mut cache = SchemaCache::default();
+        cache.validate("1", now);
+        assert!(cache.schema("public").is_none());
+
+        cache.put_schema(SchemaInfo {
+            name: "public".to_string(),
+            tables: vec![table("public", "a", &["id"]), table("public", "b", &[])],
+        });
+        assert!(cache.tables["public"].iter().all(|t| t.columns.is_empty()));
+        let schema = cache.schema("public").unwrap();
+        assert_eq!(schema.tables.len(), 2);
+        assert_eq!(schema.tables[0].columns[0].name, "id");
+
+        // A schema is only complete with the columns of every table
+        cache
+            .columns
+            .remove(&("public".to_string(), "b".to_string()));
+        assert!(cache.schema("public").is_none());
+
+        // Same catalog: kept; changed catalog: dropped
+        cache.validate("1", now);
+        assert!(cache.tables.contains_key("public"));
+        cache.validate("2", now);
+        assert!(cache.tables.is_empty() && cache.columns.is_empty());
+    }
+
+    #[test]
+    fn test_fingerprint_ttl() {
+        let now = Instant::now();
+        let mut cache = SchemaCache::default();
+        assert_eq!(cache.fresh_fingerprint(now), None);
+
+        cache.validate("1", now);
+        assert_eq!(cache.fresh_fingerprint(now).as_deref(), Some("1"));
+        let later = now + FINGERPRINT_TTL;
+        assert_eq!(cache.fresh_fingerprint(later), None);
+
+        // Reading the same fingerprint again trusts it for longer
+        cache.validate("1", later);
+        assert_eq!(cache.fresh_fingerprint(later).as_deref(), Some("1"));
+    }
+}
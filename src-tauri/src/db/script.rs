@@ -0,0 +1,588 @@
+//! Splitting and classification of SQL statements
+
+/// Splits a SQL script into individual statements.
+///
+/// Semicolons only terminate a statement at the top level, i.e. not inside
+/// string literals (including `E'...'` escapes), quoted identifiers,
+/// dollar-quoted bodies, comments, parentheses or the `BEGIN ATOMIC ... END`
+/// body of a function. Statements consisting only of whitespace and
+/// comments are dropped.
+pub fn split_statements(script: &str) -> Vec<String> {
+    let chars: Vec<char> = script.chars().collect();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+    let mut paren_depth = 0usize;
+    let mut block_depth = 0usize;
+    let mut has_content = false;
+    let mut words: Vec<String> = Vec::new();
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        match c {
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '/' if next == Some('*') => {
+                i = skip_block_comment(&chars, i);
+                continue;
+            }
+            '\'' => {
+                let escapes = i > 0
+                    && matches!(chars[i - 1], 'e' | 'E')
+                    && !is_word_char_at(&chars, i.wrapping_sub(2));
+                i = skip_quoted(&chars, i, '\'', escapes);
+                has_content = true;
+                continue;
+            }
+            '"' => {
+                i = skip_quoted(&chars, i, '"', false);
+                has_content = true;
+                continue;
+            }
+            '$' if !is_word_char_at(&chars, i.wrapping_sub(1)) => {
+                if let Some(tag_end) = dollar_tag_end(&chars, i) {
+                    let tag: String = chars[i..=tag_end].iter().collect();
+                    i = skip_dollar_quoted(&chars, tag_end + 1, &tag);
+                    has_content = true;
+                    continue;
+                }
+            }
+            '(' => paren_depth += 1,
+            ')' => paren_depth = paren_depth.saturating_sub(1),
+            ';' if paren_depth == 0 && block_depth == 0 => {
+                if has_content {
+                    statements.push(collect_trimmed(&chars[start..i]));
+                }
+                start = i + 1;
+                has_content = false;
+                words.clear();
+                i += 1;
+                continue;
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let word_start = i;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[word_start..i]
+                    .iter()
+                    .collect::<String>()
+                    .to_uppercase();
+                block_depth = track_block_depth(&words, &word, block_depth);
+                if words.len() < 4 {
+                    words.push(word);
+                }
+                has_content = true;
+                continue;
+            }
+            _ => {}
+        }
+
+        if !c.is_whitespace() {
+            has_content = true;
+        }
+        i += 1;
+    }
+
+    if has_content {
+        statements.push(collect_trimmed(&chars[start..]));
+    }
+
+    statements
+}
+
+/// Tracks `BEGIN ATOMIC ... END` bodies of SQL-standard functions and
+/// procedures, which contain semicolons of their own.
+fn track_block_depth(leading_words: &[String], word: &str, depth: usize) -> usize {
+    let is_routine = match leading_words {
+        [create, kind, ..] if create == "CREATE" && (kind == "FUNCTION" || kind == "PROCEDURE") => {
+            true
+        }
+        [create, or, replace, kind, ..]
+            if create == "CREATE" && or == "OR" && replace == "REPLACE" =>
+        {
+            kind == "FUNCTION" || kind == "PROCEDURE"
+        }
+        _ => false,
+    };
+    if !is_routine {
+        return depth;
+    }
+
+    match word {
+        "BEGIN" | "CASE" => depth + 1,
+        "END" => depth.saturating_sub(1),
+        _ => depth,
+    }
+}
+
+/// Rebuilds the command tag PostgreSQL reports for a statement, e.g.
+/// `UPDATE 42`, `INSERT 0 1` or `CREATE TABLE`.
+///
+/// sqlx only exposes the affected row count, so the tag is derived from the
+/// statement's top-level keywords. Returns `None` for an empty statement.
+pub fn command_tag(statement: &str, rows_affected: u64) -> Option<String> {
+    let words = top_level_words(statement);
+    let first = words.first()?.as_str();
+
+    let command = if first == "WITH" {
+        words
+            .iter()
+            .skip(1)
+            .map(String::as_str)
+            .find(|w| {
+                matches!(
+                    *w,
+                    "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "VALUES" | "TABLE"
+                )
+            })
+            .unwrap_or("SELECT")
+    } else {
+        first
+    };
+
+    let tag = match command {
+        "SELECT" | "VALUES" | "TABLE" => format!("SELECT {}", rows_affected),
+        "INSERT" => format!("INSERT 0 {}", rows_affected),
+        "UPDATE" | "DELETE" | "MERGE" | "COPY" | "FETCH" | "MOVE" => {
+            format!("{} {}", command, rows_affected)
+        }
+        "CREATE" if is_create_as(&words) => format!("SELECT {}", rows_affected),
+        "CREATE" | "ALTER" | "DROP" => ddl_tag(&words),
+        "TRUNCATE" => "TRUNCATE TABLE".to_string(),
+        "START" => "START TRANSACTION".to_string(),
+        "END" => "COMMIT".to_string(),
+        "ABORT" => "ROLLBACK".to_string(),
+        _ => command.to_string(),
+    };
+
+    Some(tag)
+}
+
+/// Whether a statement may change schema metadata: DDL, comments, security
+/// labels, materialized view refreshes, privilege changes, and `DO` blocks
+/// and procedure calls, which can run anything.
+pub fn changes_schema(statement: &str) -> bool {
+    let words = top_level_words(statement);
+    matches!(
+        words.first().map(String::as_str),
+        Some(
+            "CREATE"
+                | "ALTER"
+                | "DROP"
+                | "COMMENT"
+                | "SECURITY"
+                | "REFRESH"
+                | "GRANT"
+                | "REVOKE"
+                | "IMPORT"
+                | "DO"
+                | "CALL"
+        )
+    )
+}
+
+/// `CREATE TABLE ... AS` and `CREATE MATERIALIZED VIEW ... AS` report the
+/// rows they wrote, like a `SELECT`.
+fn is_create_as(words: &[String]) -> bool {
+    let creates_table = words
+        .iter()
+        .take(6)
+        .any(|w| w == "TABLE" || w == "MATERIALIZED");
+    let as_query = words.windows(2).any(|pair| {
+        pair[0] == "AS"
+            && matches!(
+                pair[1].as_str(),
+                "SELECT" | "VALUES" | "TABLE" | "WITH" | "EXECUTE"
+            )
+    });
+    creates_table && as_query
+}
+
+/// Builds `CREATE INDEX`, `DROP MATERIALIZED VIEW` and the like, skipping
+/// modifiers such as `OR REPLACE`, `TEMPORARY` or `UNIQUE`.
+fn ddl_tag(words: &[String]) -> String {
+    const MODIFIERS: &[&str] = &[
+        "OR",
+        "REPLACE",
+        "TEMP",
+        "TEMPORARY",
+        "UNLOGGED",
+        "UNIQUE",
+        "GLOBAL",
+        "LOCAL",
+        "RECURSIVE",
+        "TRUSTED",
+        "PROCEDURAL",
+        "DEFAULT",
+    ];
+    // Object types spelled with two keywords
+    const PREFIXES: &[&str] = &["MATERIALIZED", "FOREIGN", "EVENT", "ACCESS", "TEXT", "USER"];
+
+    let mut rest = words[1..]
+        .iter()
+        .map(String::as_str)
+        .skip_while(|w| MODIFIERS.contains(w));
+    let mut tag = words[0].clone();
+    if let Some(object) = rest.next() {
+        tag.push(' ');
+        tag.push_str(object);
+        if PREFIXES.contains(&object) {
+            if let Some(second) = rest.next() {
+                tag.push(' ');
+                tag.push_str(second);
+            }
+        }
+    }
+    tag
+}
+
+/// Returns the upper-cased keywords and unquoted identifiers of a statement
+/// outside parentheses, strings and comments.
+fn top_level_words(statement: &str) -> Vec<String> {
+    let chars: Vec<char> = statement.chars().collect();
+    let mut words = Vec::new();
+    let mut depth = 0usize;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+        match c {
+            '-' if next == Some('-') => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if next == Some('*') => i = skip_block_comment(&chars, i),
+            '\'' => {
+                let escapes = i > 0 && matches!(chars[i - 1], 'e' | 'E');
+                i = skip_quoted(&chars, i, '\'', escapes);
+            }
+            '"' => i = skip_quoted(&chars, i, '"', false),
+            '$' => match dollar_tag_end(&chars, i) {
+                Some(tag_end) if !is_word_char_at(&chars, i.wrapping_sub(1)) => {
+                    let tag: String = chars[i..=tag_end].iter().collect();
+                    i = skip_dollar_quoted(&chars, tag_end + 1, &tag);
+                }
+                _ => i += 1,
+            },
+            '(' => {
+                depth += 1;
+                i += 1;
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                // The E of an E'...' string is not a word
+                if chars.get(i) == Some(&'\'') && i - start == 1 {
+                    continue;
+                }
+                if depth == 0 {
+                    words.push(chars[start..i].iter().collect::<String>().to_uppercase());
+                }
+            }
+            _ => i += 1,
+        }
+    }
+
+    words
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn is_word_char_at(chars: &[char], idx: usize) -> bool {
+    chars.get(idx).is_some_and(|c| is_word_char(*c))
+}
+
+fn skip_block_comment(chars: &[char], start: usize) -> usize {
+    // Block comments nest in PostgreSQL
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+            depth += 1;
+            i += 2;
+        } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+            depth -= 1;
+            i += 2;
+            if depth == 0 {
+                return i;
+            }
+        } else {
+            i += 1;
+        }
+    }
+    i
+}
+
+fn skip_quoted(chars: &[char], start: usize, quote: char, backslash_escapes: bool) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        if backslash_escapes && chars[i] == '\\' {
+            i += 2;
+            continue;
+        }
+        if chars[i] == quote {
+            // A doubled quote is an escaped quote
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    i
+}
+
+/// Returns the index of the closing `$` if a dollar-quote tag starts at
+/// `start`, e.g. `$$` or `$body$`.
+fn dollar_tag_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut i = start + 1;
+    if chars.get(i).is_some_and(|c| c.is_ascii_digit()) {
+        // Positional parameter such as $1
+        return None;
+    }
+    while let Some(&c) = chars.get(i) {
+        if c == '$' {
+            return Some(i);
+        }
+        if !(c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        i += 1;
+    }
+    None
+}
+
+fn skip_dollar_quoted(chars: &[char], body_start: usize, tag: &str) -> usize {
+    let tag: Vec<char> = tag.chars().collect();
+    let mut i = body_start;
+    while i < chars.len() {
+        if chars[i..].starts_with(&tag) {
+            return i + tag.len();
+        }
+        i += 1;
+    }
+    i
+}
+
+fn collect_trimmed(chars: &[char]) -> String {
+    chars.iter().collect::<String>().trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_simple_statements() {
+        let statements = split_statements("SELECT 1; SELECT 2;\nSELECT 3");
+        assert_eq!(statements, vec!["SELECT 1", "SELECT 2", "SELECT 3"]);
+    }
+
+    #[test]
+    fn test_split_ignores_empty_and_comment_only_statements() {
+        let statements = split_statements(";;\n-- just a comment\n;\n/* block */;SELECT 1;");
+        assert_eq!(statements, vec!["SELECT 1"]);
+    }
+
+    #[test]
+    fn test_split_semicolons_in_strings() {
+        let statements = split_statements(
+            "INSERT INTO t VALUES ('a;b', 'it''s;'); SELECT E'\\';'; SELECT \"we;ird\" FROM t",
+        );
+        assert_eq!(
+            statements,
+            vec![
+                "INSERT INTO t VALUES ('a;b', 'it''s;')",
+                "SELECT E'\\';'",
+                "SELECT \"we;ird\" FROM t",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_semicolons_in_comments() {
+        let statements =
+            split_statements("SELECT 1 -- trailing; comment\n; /* a; /* nested; */ b; */ SELECT 2");
+        assert_eq!(
+            statements,
+            vec![
+                "SELECT 1 -- trailing; comment",
+                "/* a; /* nested; */ b; */ SELECT 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_dollar_quoted_bodies() {
+        let script = r#"
+CREATE FUNCTION f() RETURNS int AS $$
+BEGIN
+    PERFORM 1;
+    RETURN 2;
+END;
+$$ LANGUAGE plpgsql;
+DO $body$ BEGIN RAISE NOTICE '$$;'; END $body$;
+SELECT $1, a$b FROM t
+"#;
+        let statements = split_statements(script);
+        assert_eq!(statements.len(), 3);
+        assert!(statements[0].starts_with("CREATE FUNCTION"));
+        assert!(statements[0].ends_with("LANGUAGE plpgsql"));
+        assert_eq!(
+            statements[1],
+            "DO $body$ BEGIN RAISE NOTICE '$$;'; END $body$"
+        );
+        assert_eq!(statements[2], "SELECT $1, a$b FROM t");
+    }
+
+    #[test]
+    fn test_split_begin_atomic_body() {
+        let script = "CREATE OR REPLACE FUNCTION f() RETURNS int LANGUAGE sql BEGIN ATOMIC SELECT 1; SELECT CASE WHEN true THEN 2 END; END; SELECT 3";
+        let statements = split_statements(script);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].ends_with("END; END"));
+        assert_eq!(statements[1], "SELECT 3");
+    }
+
+    #[test]
+    fn test_split_transaction_keywords_outside_functions() {
+        let statements = split_statements("BEGIN; UPDATE t SET a = 1; END;");
+        assert_eq!(statements, vec!["BEGIN", "UPDATE t SET a = 1", "END"]);
+    }
+
+    #[test]
+    fn test_split_rule_actions_in_parentheses() {
+        let statements = split_statements(
+            "CREATE RULE r AS ON INSERT TO t DO ALSO (NOTIFY a; NOTIFY b); SELECT 1",
+        );
+        assert_eq!(
+            statements,
+            vec![
+                "CREATE RULE r AS ON INSERT TO t DO ALSO (NOTIFY a; NOTIFY b)",
+                "SELECT 1"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_command_tag_dml() {
+        assert_eq!(
+            command_tag("SELECT * FROM t", 3).as_deref(),
+            Some("SELECT 3")
+        );
+        assert_eq!(
+            command_tag("insert into t values (1)", 1).as_deref(),
+            Some("INSERT 0 1")
+        );
+        assert_eq!(
+            command_tag("UPDATE t SET a = 1", 42).as_deref(),
+            Some("UPDATE 42")
+        );
+        assert_eq!(
+            command_tag("-- remove\n/* old */ DELETE FROM t", 0).as_deref(),
+            Some("DELETE 0")
+        );
+        assert_eq!(
+            command_tag("VALUES (1), (2)", 2).as_deref(),
+            Some("SELECT 2")
+        );
+    }
+
+    #[test]
+    fn test_changes_schema() {
+        assert!(changes_schema("CREATE TABLE t (id int)"));
+        assert!(changes_schema("-- add a column\nalter table t add b text"));
+        assert!(changes_schema("DROP VIEW v"));
+        assert!(changes_schema("GRANT SELECT ON t TO reader"));
+        assert!(changes_schema("COMMENT ON COLUMN t.a IS 'id'"));
+        assert!(changes_schema(
+            "SECURITY LABEL FOR selinux ON TABLE t IS 'system_u:object_r:sepgsql_table_t:s0'"
+        ));
+        assert!(changes_schema("refresh materialized view concurrently mv"));
+        assert!(changes_schema(
+            "IMPORT FOREIGN SCHEMA s FROM SERVER srv INTO x"
+        ));
+        assert!(changes_schema("DO $$ BEGIN EXECUTE 'DROP TABLE t'; END $$"));
+        assert!(!changes_schema("SELECT 'CREATE TABLE t'"));
+        assert!(!changes_schema("/* DROP TABLE t */ SELECT 1"));
+        assert!(!changes_schema(
+            "WITH x AS (SELECT 1) INSERT INTO t SELECT * FROM x"
+        ));
+        assert!(!changes_schema("TRUNCATE t"));
+        assert!(!changes_schema(""));
+    }
+
+    #[test]
+    fn test_command_tag_with_query() {
+        let statement = "WITH RECURSIVE x AS (SELECT 1), y AS MATERIALIZED (DELETE FROM a RETURNING *) UPDATE t SET a = 1";
+        assert_eq!(command_tag(statement, 5).as_deref(), Some("UPDATE 5"));
+        assert_eq!(
+            command_tag(
+                "WITH x AS (UPDATE t SET a = 1 RETURNING *) SELECT * FROM x",
+                2
+            )
+            .as_deref(),
+            Some("SELECT 2")
+        );
+    }
+
+    #[test]
+    fn test_command_tag_ddl() {
+        assert_eq!(
+            command_tag("CREATE TABLE t (id int)", 0).as_deref(),
+            Some("CREATE TABLE")
+        );
+        assert_eq!(
+            command_tag(
+                "create or replace function f() returns int as $$ select 1 $$ language sql",
+                0
+            )
+            .as_deref(),
+            Some("CREATE FUNCTION")
+        );
+        assert_eq!(
+            command_tag("CREATE UNIQUE INDEX CONCURRENTLY i ON t (a)", 0).as_deref(),
+            Some("CREATE INDEX")
+        );
+        assert_eq!(
+            command_tag("DROP MATERIALIZED VIEW IF EXISTS v", 0).as_deref(),
+            Some("DROP MATERIALIZED VIEW")
+        );
+        assert_eq!(
+            command_tag("CREATE TEMP TABLE t AS SELECT * FROM s", 7).as_deref(),
+            Some("SELECT 7")
+        );
+        assert_eq!(
+            command_tag("TRUNCATE t", 0).as_deref(),
+            Some("TRUNCATE TABLE")
+        );
+        assert_eq!(command_tag("begin", 0).as_deref(), Some("BEGIN"));
+    }
+
+    #[test]
+    fn test_command_tag_ignores_quoted_words() {
+        assert_eq!(
+            command_tag("/* UPDATE */ SELECT 'DELETE', \"INSERT\" FROM t", 1).as_deref(),
+            Some("SELECT 1")
+        );
+        assert_eq!(command_tag("-- only a comment", 0), None);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::types::TransactionStatus;
+use sqlx::postgres::PgConnection;
+use sqlx::{Connection, Executor, Row};
+
+/// SQLSTATE `in_failed_sql_transaction`
+const IN_FAILED_SQL_TRANSACTION: &str = "25P02";
+
+/// A dedicated backend for statements typed by the user.
+///
+/// Unlike pool connections, the same backend serves every call, so
+/// transactions, `SET` and temporary tables persist between statements.
+pub struct Session {
+    pub conn: PgConnection,
+    pub backend_pid: i32,
+    pub status: TransactionStatus,
+}
+
+impl Session {
+    pub async fn open(mut conn: PgConnection) -> Result<Self, sqlx::Error> {
+        let backend_pid: i32 = sqlx::query_scalar("SELECT pg_backend_pid()")
+            .fetch_one(&mut conn)
+            .await?;
+
+        Ok(Self {
+            conn,
+            backend_pid,
+            status: TransactionStatus::Idle,
+        })
+    }
+
+    /// Runs a transaction control statement such as `BEGIN` and refreshes
+    /// the status afterwards, whether or not it succeeded.
+    pub async fn control(&mut self, statement: &str) -> Result<(), sqlx::Error> {
+        let result = self.conn.execute(statement).await;
+        self.refresh_status().await;
+        result.map(|_| ())
+    }
+
+    /// Probes the backend for its transaction state.
+    ///
+    /// The statements may have opened or closed a transaction themselves
+    /// (`BEGIN`, `COMMIT`, a failing statement), so the status cannot be
+    /// tracked from our own commands alone. The probe uses the simple query
+    /// protocol: outside a transaction block it runs in its own implicit
+    /// transaction, whose start time equals the statement start time.
+    pub async fn refresh_status(&mut self) {
+        let probe = self
+            .conn
+            .fetch_one("SELECT transaction_timestamp() <> statement_timestamp()")
+            .await
+            .and_then(|row| row.try_get::<bool, _>(0));
+
+        self.status = match probe {
+            Ok(true) => TransactionStatus::InTransaction,
+            Ok(false) => TransactionStatus::Idle,
+            Err(sqlx::Error::Database(e))
+                if e.code().as_deref() == Some(IN_FAILED_SQL_TRANSACTION) =>
+            {
+                TransactionStatus::Failed
+            }
+            Err(_) => TransactionStatus::Unknown,
+        };
+    }
+
+    pub async fn close(self) {
+        let _ = self.conn.close().await;
+    }
+}
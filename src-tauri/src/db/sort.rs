@@ -0,0 +1,120 @@
+//! Compilation of table data sort keys into an `ORDER BY` clause
+
+use crate::db::binding::find_column;
+use crate::db::sql_utils::safe_identifier;
+use crate::error::AppError;
+use crate::types::{NullsOrder, SortDirection, SortKey, TableColumnInfo};
+
+/// Returns the effective sort order for a table: the requested keys followed
+/// by any columns of the table's unique key they don't already cover.
+///
+/// Ending on a unique key makes the order total, so rows with equal sort
+/// values can't swap places between pages.
+pub fn effective_sort(sort: &[SortKey], key_columns: &[String]) -> Vec<SortKey> {
+    let mut keys = sort.to_vec();
+    for name in key_columns {
+        if !keys.iter().any(|k| &k.column == name) {
+            keys.push(SortKey {
+                column: name.clone(),
+                direction: SortDirection::Asc,
+                nulls: None,
+            });
+        }
+    }
+    keys
+}
+
+/// Compiles sort keys into ` ORDER BY ...`, or an empty string if there are
+/// none.
+pub fn order_by_clause(sort: &[SortKey], columns: &[TableColumnInfo]) -> Result<String, AppError> {
+    if sort.is_empty() {
+        return Ok(String::new());
+    }
+
+    let keys = sort
+        .iter()
+        .map(|key| {
+            let column = find_column(columns, &key.column)?;
+            let mut clause = safe_identifier(&column.name)?;
+            if key.direction == SortDirection::Desc {
+                clause.push_str(" DESC");
+            }
+            match key.nulls {
+                Some(NullsOrder::First) => clause.push_str(" NULLS FIRST"),
+                Some(NullsOrder::Last) => clause.push_str(" NULLS LAST"),
+                None => {}
+            }
+            Ok(clause)
+        })
+        .collect::<Result<Vec<_>, AppError>>()?;
+
+    Ok(format!(" ORDER BY {}", keys.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn column(name: &str, is_primary_key: bool) -> TableColumnInfo {
+        TableColumnInfo {
+            name: name.to_string(),
+            data_type: "integer".to_string(),
+            sql_type: "integer".to_string(),
+            is_nullable: !is_primary_key,
+            is_primary_key,
+            is_auto_generated: false,
+            default_value: None,
+        }
+    }
+
+    fn key(column: &str, direction: SortDirection, nulls: Option<NullsOrder>) -> SortKey {
+        SortKey {
+            column: column.to_string(),
+            direction,
+            nulls,
+        }
+    }
+
+    #[test]
+    fn test_default_to_key() {
+        let columns = vec![column("a", true), column("b", true), column("c", false)];
+        let sort = effective_sort(&[], &["a".to_string(), "b".to_string()]);
+        assert_eq!(
+            order_by_clause(&sort, &columns).unwrap(),
+            " ORDER BY \"a\", \"b\""
+        );
+
+        let sort = effective_sort(&[], &[]);
+        assert_eq!(order_by_clause(&sort, &columns).unwrap(), "");
+    }
+
+    #[test]
+    fn test_keys_with_tie_breaker() {
+        let columns = vec![column("id", true), column("name", false)];
+        let sort = effective_sort(
+            &[key("name", SortDirection::Desc, Some(NullsOrder::Last))],
+            &["id".to_string()],
+        );
+        assert_eq!(
+            order_by_clause(&sort, &columns).unwrap(),
+            " ORDER BY \"name\" DESC NULLS LAST, \"id\""
+        );
+
+        // A sort key already on the unique key keeps its direction
+        let sort = effective_sort(
+            &[key("id", SortDirection::Desc, Some(NullsOrder::First))],
+            &["id".to_string()],
+        );
+        assert_eq!(
+            order_by_clause(&sort, &columns).unwrap(),
+            " ORDER BY \"id\" DESC NULLS FIRST"
+        );
+    }
+
+    #[test]
+    fn test_unknown_sort_column() {
+        let columns = vec![column("id", true)];
+        let sort = [key("missing\" DESC; --", SortDirection::Asc, None)];
+        assert!(order_by_clause(&sort, &columns).is_err());
+    }
+}
@@ -1,47 +1,57 @@
 use crate::error::AppError;
 
-/// Validates a SQL identifier (schema name, table name, column name)
-/// to prevent SQL injection attacks.
+/// Longest identifier PostgreSQL keeps, `NAMEDATALEN - 1`
+const MAX_IDENTIFIER_BYTES: usize = 63;
+
+/// Validates a SQL identifier (schema name, table name, column name).
 ///
-/// Valid identifiers:
-/// - Start with a letter or underscore
-/// - Contain only letters, numbers, underscores
-/// - Are not empty and not too long (max 128 chars)
+/// Any name PostgreSQL accepts as a quoted identifier is valid: names may
+/// contain spaces, dots, quotes or upper-case letters, as long as they're
+/// quoted with [`quote_identifier`]. Only names that can't be written at all
+/// are rejected: empty names, names containing NUL, and names longer than
+/// PostgreSQL keeps, which it would silently truncate.
 pub fn validate_identifier(name: &str) -> Result<&str, AppError> {
     if name.is_empty() {
         return Err(AppError::InvalidConfig("Identifier cannot be empty".into()));
     }
 
-    if name.len() > 128 {
-        return Err(AppError::InvalidConfig(
-            "Identifier too long (max 128 characters)".into(),
-        ));
-    }
-
-    let first_char = name.chars().next().unwrap();
-    if !first_char.is_alphabetic() && first_char != '_' {
+    if name.len() > MAX_IDENTIFIER_BYTES {
         return Err(AppError::InvalidConfig(format!(
-            "Invalid identifier '{}': must start with a letter or underscore",
-            name
+            "Identifier too long (max {} bytes)",
+            MAX_IDENTIFIER_BYTES
         )));
     }
 
-    for c in name.chars() {
-        if !c.is_alphanumeric() && c != '_' {
-            return Err(AppError::InvalidConfig(format!(
-                "Invalid identifier '{}': contains invalid character '{}'",
-                name, c
-            )));
-        }
+    if name.contains('\0') {
+        return Err(AppError::InvalidConfig(format!(
+            "Invalid identifier '{}': contains a NUL character",
+            name.escape_default()
+        )));
     }
 
     Ok(name)
 }
 
-/// Quotes an identifier for safe use in SQL queries.
+/// Quotes an identifier for safe use in SQL queries, like PostgreSQL's
+/// `quote_ident` but always quoting, so case is preserved. Embedded double
+/// quotes are doubled.
+///
 /// This should only be called after validate_identifier().
 pub fn quote_identifier(name: &str) -> String {
-    format!("\"{}\"", name)
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Quotes a string as a SQL literal, like PostgreSQL's `quote_literal`.
+///
+/// Strings containing backslashes use the `E'...'` form, so the literal
+/// reads the same whatever `standard_conforming_strings` is set to.
+pub fn quote_literal(value: &str) -> String {
+    let quoted = value.replace('\'', "''");
+    if value.contains('\\') {
+        format!("E'{}'", quoted.replace('\\', "\\\\"))
+    } else {
+        format!("'{}'", quoted)
+    }
 }
 
 /// Validates and quotes an identifier in one step.
@@ -51,6 +61,8 @@ pub fn safe_identifier(name: &str) -> Result<String, AppError> {
 }
 
 /// Validates and quotes a schema.table reference.
+///
+/// Each part is quoted on its own, so a dot inside a name never splits it.
 pub fn safe_table_ref(schema: &str, table: &str) -> Result<String, AppError> {
     let safe_schema = safe_identifier(schema)?;
     let safe_table = safe_identifier(table)?;
@@ -68,30 +80,104 @@ mod tests {
         assert!(validate_identifier("table_name").is_ok());
         assert!(validate_identifier("Table123").is_ok());
         assert!(validate_identifier("public").is_ok());
+        // Legal as quoted identifiers
+        assert!(validate_identifier("123abc").is_ok());
+        assert!(validate_identifier("order-items").is_ok());
+        assert!(validate_identifier("Customer Data").is_ok());
+        assert!(validate_identifier("a.b").is_ok());
+        assert!(validate_identifier("price$").is_ok());
+        assert!(validate_identifier("table\"quote").is_ok());
+        assert!(validate_identifier("table'quote").is_ok());
+        assert!(validate_identifier("日本語").is_ok());
     }
 
     #[test]
     fn test_invalid_identifiers() {
         assert!(validate_identifier("").is_err());
-        assert!(validate_identifier("123abc").is_err());
-        assert!(validate_identifier("table-name").is_err());
-        assert!(validate_identifier("table name").is_err());
-        assert!(validate_identifier("table;drop").is_err());
-        assert!(validate_identifier("table\"quote").is_err());
-        assert!(validate_identifier("table'quote").is_err());
+        assert!(validate_identifier("table\0name").is_err());
+        assert!(validate_identifier(&"a".repeat(63)).is_ok());
+        assert!(validate_identifier(&"a".repeat(64)).is_err());
+        // The limit is in bytes: 21 three-byte characters fit, 22 don't
+        assert!(validate_identifier(&"日".repeat(21)).is_ok());
+        assert!(validate_identifier(&"日".repeat(22)).is_err());
+        assert!(safe_identifier("\0").is_err());
+        assert!(safe_table_ref("public", "users\0").is_err());
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(quote_identifier("users"), "\"users\"");
+        // Case is preserved
+        assert_eq!(quote_identifier("Users"), "\"Users\"");
+        assert_eq!(quote_identifier("order-items"), "\"order-items\"");
+        assert_eq!(quote_identifier("Customer Data"), "\"Customer Data\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+        assert_eq!(quote_identifier("\""), "\"\"\"\"");
+        assert_eq!(quote_identifier("a.b"), "\"a.b\"");
     }
 
     #[test]
     fn test_safe_table_ref() {
         let result = safe_table_ref("public", "users").unwrap();
         assert_eq!(result, "\"public\".\"users\"");
+        let result = safe_table_ref("Sales", "order-items").unwrap();
+        assert_eq!(result, "\"Sales\".\"order-items\"");
+    }
+
+    #[test]
+    fn test_quote_literal() {
+        assert_eq!(quote_literal("abc"), "'abc'");
+        assert_eq!(
+            quote_literal("x'; DROP TABLE users; --"),
+            "'x''; DROP TABLE users; --'"
+        );
+        assert_eq!(quote_literal(r"a\'b"), r"E'a\\''b'");
+    }
+
+    /// Checks that `quoted` is a single quoted identifier: it starts and
+    /// ends with a quote and every quote inside is doubled.
+    fn is_single_identifier(quoted: &str) -> bool {
+        let inner = match quoted.strip_prefix('"').and_then(|q| q.strip_suffix('"')) {
+            Some(inner) => inner,
+            None => return false,
+        };
+        inner.replace("\"\"", "").find('"').is_none()
     }
 
     #[test]
     fn test_injection_attempt() {
-        // Attempting SQL injection via schema name
-        assert!(safe_table_ref("public\"; DROP TABLE users; --", "users").is_err());
+        // Attempting SQL injection via schema name: the quote is doubled, so
+        // the whole input stays part of the schema name
+        assert_eq!(
+            safe_table_ref("public\"; DROP TABLE users; --", "users").unwrap(),
+            "\"public\"\"; DROP TABLE users; --\".\"users\""
+        );
         // Attempting SQL injection via table name
-        assert!(safe_table_ref("public", "users\"; DROP TABLE users; --").is_err());
+        assert_eq!(
+            safe_table_ref("public", "users\"; DROP TABLE users; --").unwrap(),
+            "\"public\".\"users\"\"; DROP TABLE users; --\""
+        );
+        // Attempting to end the identifier early with an escaped quote, which
+        // identifiers don't have
+        assert_eq!(safe_identifier("x\\\"y").unwrap(), "\"x\\\"\"y\"");
+        // Attempting to inject through a NUL terminator
+        assert!(safe_identifier("users\0\"; DROP TABLE users; --").is_err());
+
+        for name in [
+            "\"",
+            "\"\"",
+            "a\"\"b",
+            "\"; DROP TABLE users; --",
+            "users\".\"other",
+            "x' OR '1'='1",
+            "a\\\"",
+            "\"\";--",
+        ] {
+            let quoted = safe_identifier(name).unwrap();
+            assert!(is_single_identifier(&quoted), "{} -> {}", name, quoted);
+            // Undoing the quoting gives back the original name
+            let inner = &quoted[1..quoted.len() - 1];
+            assert_eq!(inner.replace("\"\"", "\""), name);
+        }
     }
 }
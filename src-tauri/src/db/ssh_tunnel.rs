@@ -0,0 +1,253 @@
+use crate::error::AppError;
+use crate::types::{SshAuthMethod, SshTunnelConfig};
+use russh::client::{self, Handle};
+use russh::keys::known_hosts::learn_known_hosts_path;
+use russh::keys::{self, PrivateKeyWithHashAlg};
+use russh::Disconnect;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+struct TunnelClient {
+    host: String,
+    port: u16,
+}
+
+impl client::Handler for TunnelClient {
+    type Error = AppError;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &keys::PublicKey,
+    ) -> Result<bool, Self::Error> {
+        let known_hosts = known_hosts_path().ok_or_else(|| {
+            AppError::SshTunnel("Cannot locate known_hosts: no home directory".to_string())
+        })?;
+        verify_host_key(&self.host, self.port, server_public_key, &known_hosts)?;
+        Ok(true)
+    }
+}
+
+fn known_hosts_path() -> Option<PathBuf> {
+    std::env::home_dir().map(|home| home.join(".ssh").join("known_hosts"))
+}
+
+/// Checks a server key against `known_hosts`, trusting it on first use.
+///
+/// A key seen for the first time is recorded, so a later change is caught.
+/// A changed key, or a `known_hosts` that can't be read or written, rejects
+/// the connection.
+fn verify_host_key(
+    host: &str,
+    port: u16,
+    key: &keys::PublicKey,
+    known_hosts: &Path,
+) -> Result<(), AppError> {
+    match keys::check_known_hosts_path(host, port, key, known_hosts) {
+        Ok(true) => Ok(()),
+        Ok(false) => learn_known_hosts_path(host, port, key, known_hosts).map_err(|e| {
+            AppError::SshTunnel(format!(
+                "Failed to record the host key of {} in {}: {}",
+                host,
+                known_hosts.display(),
+                e
+            ))
+        }),
+        Err(keys::Error::KeyChanged { line }) => Err(AppError::SshTunnel(format!(
+            "The host key of {} does not match the one recorded in {} (line {}); \
+             the host may be impersonated",
+            host,
+            known_hosts.display(),
+            line
+        ))),
+        Err(e) => Err(AppError::SshTunnel(format!(
+            "Failed to check the host key of {} against {}: {}",
+            host,
+            known_hosts.display(),
+            e
+        ))),
+    }
+}
+
+/// A local port forward through an SSH jump host.
+///
+/// Connections accepted on `127.0.0.1:<local_port>` are forwarded to the
+/// database host as seen from the jump host.
+pub struct SshTunnel {
+    session: Arc<Handle<TunnelClient>>,
+    local_port: u16,
+    listener_task: JoinHandle<()>,
+}
+
+impl SshTunnel {
+    pub async fn open(
+        config: &SshTunnelConfig,
+        target_host: &str,
+        target_port: u16,
+    ) -> Result<Self, AppError> {
+        let session = Arc::new(authenticate(config).await?);
+
+        let listener = TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .map_err(|e| AppError::SshTunnel(format!("Failed to bind local port: {}", e)))?;
+        let local_port = listener
+            .local_addr()
+            .map_err(|e| AppError::SshTunnel(e.to_string()))?
+            .port();
+
+        let forward_session = session.clone();
+        let target_host = target_host.to_string();
+        let listener_task = tokio::spawn(async move {
+            while let Ok((mut socket, peer)) = listener.accept().await {
+                let session = forward_session.clone();
+                let target_host = target_host.clone();
+                tokio::spawn(async move {
+                    let channel = match session
+                        .channel_open_direct_tcpip(
+                            target_host,
+                            u32::from(target_port),
+                            peer.ip().to_string(),
+                            u32::from(peer.port()),
+                        )
+                        .await
+                    {
+                        Ok(channel) => channel,
+                        Err(_) => return,
+                    };
+                    let mut stream = channel.into_stream();
+                    let _ = tokio::io::copy_bidirectional(&mut socket, &mut stream).await;
+                });
+            }
+        });
+
+        Ok(Self {
+            session,
+            local_port,
+            listener_task,
+        })
+    }
+
+    pub fn local_port(&self) -> u16 {
+        self.local_port
+    }
+
+    /// Stops accepting new connections and closes the SSH session.
+    pub async fn close(&self) {
+        self.listener_task.abort();
+        let _ = self
+            .session
+            .disconnect(Disconnect::ByApplication, "", "en")
+            .await;
+    }
+}
+
+impl Drop for SshTunnel {
+    fn drop(&mut self) {
+        self.listener_task.abort();
+    }
+}
+
+async fn authenticate(config: &SshTunnelConfig) -> Result<Handle<TunnelClient>, AppError> {
+    let ssh_config = Arc::new(client::Config {
+        nodelay: true,
+        ..Default::default()
+    });
+    let handler = TunnelClient {
+        host: config.host.clone(),
+        port: config.port,
+    };
+
+    let mut session = client::connect(ssh_config, (config.host.as_str(), config.port), handler)
+        .await
+        .map_err(|e| {
+            let reason = match e {
+                AppError::SshTunnel(reason) => reason,
+                e => e.to_string(),
+            };
+            AppError::SshTunnel(format!(
+                "Failed to connect to {}:{}: {}",
+                config.host, config.port, reason
+            ))
+        })?;
+
+    let auth = match config.auth_method {
+        SshAuthMethod::Password => session
+            .authenticate_password(&config.username, &config.password)
+            .await
+            .map_err(|e| AppError::SshTunnel(e.to_string()))?,
+        SshAuthMethod::PrivateKey => {
+            let path = config.private_key_path.as_deref().ok_or_else(|| {
+                AppError::InvalidConfig("SSH private key path is required".to_string())
+            })?;
+            let passphrase = (!config.passphrase.is_empty()).then_some(config.passphrase.as_str());
+            let key = keys::load_secret_key(path, passphrase).map_err(|e| {
+                AppError::SshTunnel(format!("Failed to load private key '{}': {}", path, e))
+            })?;
+            let hash_alg = session
+                .best_supported_rsa_hash()
+                .await
+                .map_err(|e| AppError::SshTunnel(e.to_string()))?
+                .flatten();
+            session
+                .authenticate_publickey(
+                    &config.username,
+                    PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg),
+                )
+                .await
+                .map_err(|e| AppError::SshTunnel(e.to_string()))?
+        }
+    };
+
+    if !auth.success() {
+        return Err(AppError::SshTunnel(format!(
+            "Authentication failed for {}@{}",
+            config.username, config.host
+        )));
+    }
+
+    Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAINYDunMq22mCIMBzFwm2qR0AFdmUnW6effnGHL1Rb8QY";
+    const OTHER_KEY: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIH7TwGhL7w1IzE4y1sozrIQ1LApaRuAfh//EACv8KzWr";
+
+    fn public_key(openssh: &str) -> keys::PublicKey {
+        keys::PublicKey::from_openssh(openssh).unwrap()
+    }
+
+    #[test]
+    fn test_verify_host_key() {
+        let dir = std::env::temp_dir().join(format!("known-hosts-{}", uuid::Uuid::new_v4()));
+        let known_hosts = dir.join("known_hosts");
+        let key = public_key(KEY);
+
+        // Unknown hosts are trusted on first use and recorded
+        verify_host_key("bastion", 2222, &key, &known_hosts).unwrap();
+        let recorded = std::fs::read_to_string(&known_hosts).unwrap();
+        assert!(recorded
+            .lines()
+            .any(|line| line.starts_with("[bastion]:2222 ssh-ed25519 ")));
+        verify_host_key("bastion", 2222, &key, &known_hosts).unwrap();
+
+        // A changed key is rejected, and not recorded
+        let err = verify_host_key("bastion", 2222, &public_key(OTHER_KEY), &known_hosts);
+        assert!(matches!(err, Err(AppError::SshTunnel(m)) if m.contains("does not match")));
+        assert_eq!(std::fs::read_to_string(&known_hosts).unwrap(), recorded);
+
+        // The same key on another port is another host
+        verify_host_key("bastion", 22, &public_key(OTHER_KEY), &known_hosts).unwrap();
+
+        // An unreadable known_hosts rejects the key
+        std::fs::write(&known_hosts, "bastion ssh-ed25519 not-base64\n").unwrap();
+        assert!(verify_host_key("bastion", 22, &key, &known_hosts).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,376 @@
+//! Undo log of data edits applied through the table editor
+//!
+//! Every edit command records, for each row it wrote, the row as it was
+//! before (nothing for inserted rows) and as it was left (nothing for
+//! deleted rows). Undoing an entry writes the before-images back, but only
+//! to rows that are still exactly as the edit left them.
+
+use crate::db::binding::{find_column, param_text, Params};
+use crate::db::row_key::RowKey;
+use crate::db::sql_utils::safe_identifier;
+use crate::error::AppError;
+use crate::types::{ChangeRecord, TableColumnInfo, TableRow};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use sqlx::PgPool;
+
+/// Entries kept per connection; older ones are dropped
+const MAX_ENTRIES: usize = 100;
+
+/// One applied edit command and the rows it wrote.
+#[derive(Debug, Clone)]
+pub struct UndoEntry {
+    pub id: u32,
+    pub schema: String,
+    pub table: String,
+    pub applied_at: DateTime<Utc>,
+    /// Names of the columns, in the order of row values
+    pub columns: Vec<String>,
+    /// Rows in the order they were first written
+    pub rows: Vec<RowImage>,
+}
+
+/// One row before and after an edit.
+#[derive(Debug, Clone, Default)]
+pub struct RowImage {
+    pub before: Option<TableRow>,
+    pub after: Option<TableRow>,
+}
+
+impl UndoEntry {
+    pub fn record(&self) -> ChangeRecord {
+        let count = |f: fn(&RowImage) -> bool| self.rows.iter().filter(|row| f(row)).count();
+        ChangeRecord {
+            id: self.id,
+            schema: self.schema.clone(),
+            table: self.table.clone(),
+            applied_at: self.applied_at.to_rfc3339(),
+            inserted: count(|row| row.before.is_none()),
+            updated: count(|row| row.before.is_some() && row.after.is_some()),
+            deleted: count(|row| row.after.is_none()),
+        }
+    }
+}
+
+/// The undo entries of one connection, oldest first.
+#[derive(Debug, Default)]
+pub struct UndoLog {
+    entries: Vec<UndoEntry>,
+    next_id: u32,
+}
+
+impl UndoLog {
+    /// Records an applied edit. Edits that wrote no rows aren't recorded.
+    pub fn push(&mut self, schema: &str, table: &str, columns: Vec<String>, rows: Vec<RowImage>) {
+        if rows.is_empty() {
+            return;
+        }
+
+        self.next_id += 1;
+        self.entries.push(UndoEntry {
+            id: self.next_id,
+            schema: schema.to_string(),
+            table: table.to_string(),
+            applied_at: Utc::now(),
+            columns,
+            rows,
+        });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.remove(0);
+        }
+    }
+
+    pub fn last(&self) -> Option<&UndoEntry> {
+        self.entries.last()
+    }
+
+    pub fn pop(&mut self) -> Option<UndoEntry> {
+        self.entries.pop()
+    }
+
+    /// Points older entries that left a row as `old` at `new`, the row as an
+    /// undo rewrote it, so they can still be undone.
+    pub fn replace_row(&mut self, old: &TableRow, new: &TableRow) {
+        let images = self.entries.iter_mut().flat_map(|e| e.rows.iter_mut());
+        for after in images.filter_map(|row| row.after.as_mut()) {
+            if after.id == old.id && after.xmin == old.xmin {
+                *after = new.clone();
+            }
+        }
+    }
+
+    /// Summaries of the entries, newest first.
+    pub fn records(&self) -> Vec<ChangeRecord> {
+        self.entries.iter().rev().map(UndoEntry::record).collect()
+    }
+}
+
+/// Lists the generated columns of a table.
+pub async fn generated_columns(
+    pool: &PgPool,
+    schema: &str,
+    table: &str,
+) -> Result<Vec<String>, AppError> {
+    let columns = sqlx::query_scalar(
+        "SELECT attname::text FROM pg_catalog.pg_attribute \
+         WHERE attrelid = format('%I.%I', $1::text, $2::text)::regclass \
+           AND attnum > 0 AND NOT attisdropped AND attgenerated <> ''",
+    )
+    .bind(schema)
+    .bind(table)
+    .fetch_all(pool)
+    .await?;
+    Ok(columns)
+}
+
+/// A table as it is when an entry is undone
+pub struct UndoTarget<'a> {
+    pub table_ref: &'a str,
+    pub columns: &'a [TableColumnInfo],
+    pub key: &'a RowKey,
+    /// Generated columns, which can't be written
+    pub generated: &'a [String],
+}
+
+/// Compiles the statement reverting one row of `entry`, or `None` if the
+/// row needs no change.
+///
+/// Inserted rows are deleted and deleted rows inserted again, keeping their
+/// identity values. Updated rows get back the values that differ. Deletes
+/// and updates only match a row whose `xmin` is still the one the edit
+/// left. Every statement returns the row it wrote, so a statement that
+/// returns nothing found the row changed.
+pub fn inverse_statement(
+    entry: &UndoEntry,
+    image: &RowImage,
+    target: &UndoTarget,
+    params: &mut Params,
+) -> Result<Option<String>, AppError> {
+    match (&image.before, &image.after) {
+        (None, Some(after)) => Ok(Some(format!(
+            "DELETE FROM {} WHERE {} RETURNING {}",
+            target.table_ref,
+            unchanged_row(after, target, params)?,
+            target.key.select_list()
+        ))),
+        (Some(before), Some(after)) => {
+            let mut assignments = Vec::new();
+            for (column, value) in writable_values(entry, before, target)? {
+                if after_value(entry, after, &column.name) == Some(value) {
+                    continue;
+                }
+                assignments.push(format!(
+                    "{} = {}",
+                    safe_identifier(&column.name)?,
+                    params.push(column, param_text(column, value)?)
+                ));
+            }
+            if assignments.is_empty() {
+                return Ok(None);
+            }
+
+            Ok(Some(format!(
+                "UPDATE {} SET {} WHERE {} RETURNING {}",
+                target.table_ref,
+                assignments.join(", "),
+                unchanged_row(after, target, params)?,
+                target.key.select_list()
+            )))
+        }
+        (Some(before), None) => {
+            let mut names = Vec::new();
+            let mut values = Vec::new();
+            for (column, value) in writable_values(entry, before, target)? {
+                names.push(safe_identifier(&column.name)?);
+                values.push(params.push(column, param_text(column, value)?));
+            }
+
+            Ok(Some(format!(
+                "INSERT INTO {} ({}) OVERRIDING SYSTEM VALUE VALUES ({}) RETURNING {}",
+                target.table_ref,
+                names.join(", "),
+                values.join(", "),
+                target.key.select_list()
+            )))
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Values of a row image for the columns that can be written, matched to
+/// the table's current columns by name.
+fn writable_values<'a>(
+    entry: &UndoEntry,
+    row: &'a TableRow,
+    target: &UndoTarget<'a>,
+) -> Result<Vec<(&'a TableColumnInfo, &'a Value)>, AppError> {
+    entry
+        .columns
+        .iter()
+        .zip(&row.values)
+        .filter(|(name, _)| !target.generated.contains(name))
+        .map(|(name, value)| Ok((find_column(target.columns, name)?, value)))
+        .collect()
+}
+
+fn after_value<'a>(entry: &UndoEntry, row: &'a TableRow, name: &str) -> Option<&'a Value> {
+    let idx = entry.columns.iter().position(|c| c == name)?;
+    row.values.get(idx)
+}
+
+/// Condition matching the row `after` while it's unchanged since the edit.
+fn unchanged_row(
+    after: &TableRow,
+    target: &UndoTarget,
+    params: &mut Params,
+) -> Result<String, AppError> {
+    let mut condition = target.key.where_clause(&after.id, target.columns, params)?;
+    if let Some(xmin) = &after.xmin {
+        condition.push_str(&format!(
+            " AND xmin::text = {}",
+            params.push_text(xmin.clone())
+        ));
+    }
+    Ok(condition)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RowKeyKind;
+    use serde_json::json;
+
+    fn column(name: &str, sql_type: &str) -> TableColumnInfo {
+        TableColumnInfo {
+            name: name.to_string(),
+            data_type: sql_type.to_string(),
+            sql_type: sql_type.to_string(),
+            is_nullable: true,
+            is_primary_key: name == "id",
+            is_auto_generated: false,
+            default_value: None,
+        }
+    }
+
+    fn row(id: i64, values: Value, xmin: &str) -> TableRow {
+        TableRow {
+            id: format!(r#"{{"kind":"primary_key","values":{{"id":{}}}}}"#, id),
+            values: serde_json::from_value(values).unwrap(),
+            xmin: Some(xmin.to_string()),
+        }
+    }
+
+    fn entry(rows: Vec<RowImage>) -> UndoEntry {
+        UndoEntry {
+            id: 1,
+            schema: "public".to_string(),
+            table: "t".to_string(),
+            applied_at: Utc::now(),
+            columns: vec!["id".to_string(), "name".to_string(), "total".to_string()],
+            rows,
+        }
+    }
+
+    fn inverse(image: RowImage) -> (Option<String>, Params) {
+        let columns = vec![
+            column("id", "integer"),
+            column("name", "text"),
+            column("total", "numeric"),
+        ];
+        let key = RowKey {
+            kind: RowKeyKind::PrimaryKey,
+            columns: vec!["id".to_string()],
+        };
+        let generated = vec!["total".to_string()];
+        let target = UndoTarget {
+            table_ref: "\"public\".\"t\"",
+            columns: &columns,
+            key: &key,
+            generated: &generated,
+        };
+
+        let entry = entry(vec![image.clone()]);
+        let mut params = Params::default();
+        let sql = inverse_statement(&entry, &image, &target, &mut params).unwrap();
+        (sql, params)
+    }
+
+    #[test]
+    fn test_inverse_statements() {
+        let (sql, _) = inverse(RowImage {
+            before: None,
+            after: Some(row(7, json!([7, "a", 1]), "100")),
+        });
+        assert_eq!(
+            sql.unwrap(),
+            "DELETE FROM \"public\".\"t\" WHERE \"id\" = $1::integer AND xmin::text = $2::text \
+             RETURNING *, xmin::text AS xmin"
+        );
+
+        // Only changed columns are restored; generated ones never are
+        let (sql, params) = inverse(RowImage {
+            before: Some(row(7, json!([7, "a", 1]), "100")),
+            after: Some(row(7, json!([7, "b", 2]), "101")),
+        });
+        assert_eq!(
+            sql.unwrap(),
+            "UPDATE \"public\".\"t\" SET \"name\" = $1::text \
+             WHERE \"id\" = $2::integer AND xmin::text = $3::text \
+             RETURNING *, xmin::text AS xmin"
+        );
+        assert_eq!(
+            params.values(),
+            [
+                Some("a".to_string()),
+                Some("7".to_string()),
+                Some("101".to_string())
+            ]
+        );
+
+        let (sql, _) = inverse(RowImage {
+            before: Some(row(7, json!([7, null, 1]), "100")),
+            after: None,
+        });
+        assert_eq!(
+            sql.unwrap(),
+            "INSERT INTO \"public\".\"t\" (\"id\", \"name\") OVERRIDING SYSTEM VALUE \
+             VALUES ($1::integer, $2::text) RETURNING *, xmin::text AS xmin"
+        );
+
+        let (sql, _) = inverse(RowImage {
+            before: Some(row(7, json!([7, "a", 1]), "100")),
+            after: Some(row(7, json!([7, "a", 1]), "101")),
+        });
+        assert_eq!(sql, None);
+    }
+
+    #[test]
+    fn test_undo_log() {
+        let mut log = UndoLog::default();
+        log.push("public", "t", vec![], vec![]);
+        assert!(log.last().is_none());
+
+        for _ in 0..MAX_ENTRIES + 1 {
+            log.push(
+                "public",
+                "t",
+                vec!["id".to_string()],
+                vec![RowImage {
+                    before: None,
+                    after: Some(row(1, json!([1]), "1")),
+                }],
+            );
+        }
+        let records = log.records();
+        assert_eq!(records.len(), MAX_ENTRIES);
+        assert_eq!(records[0].id, MAX_ENTRIES as u32 + 1);
+        assert_eq!(records[0].inserted, 1);
+        assert_eq!(log.pop().map(|e| e.id), Some(MAX_ENTRIES as u32 + 1));
+        assert_eq!(log.last().map(|e| e.id), Some(MAX_ENTRIES as u32));
+
+        // Undoing a newer entry moves older ones onto the rewritten row
+        let new = row(1, json!([1]), "2");
+        log.replace_row(&row(1, json!([1]), "1"), &new);
+        assert_eq!(log.last().unwrap().rows[0].after, Some(new));
+    }
+}
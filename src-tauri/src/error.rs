@@ -7,14 +7,47 @@ pub enum AppError {
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
+    #[error("Query cancelled: {0}")]
+    QueryCancelled(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
+
+    #[error("Transaction error: {0}")]
+    Transaction(String),
+
     #[error("Connection not found: {0}")]
     ConnectionNotFound(String),
 
-    #[error("Already connected")]
-    AlreadyConnected,
+    #[error("Already connected: {0}")]
+    AlreadyConnected(String),
+
+    #[error("Certificate error: {0}")]
+    Certificate(String),
+
+    #[error("SSH tunnel error: {0}")]
+    SshTunnel(String),
+
+    #[error("Invalid value for column \"{column}\": {message}")]
+    InvalidValue { column: String, message: String },
+
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("Invalid cursor: {0}")]
+    InvalidCursor(String),
+
+    #[error("Conflict: {0}")]
+    Conflict(String),
 
-    #[error("Not connected")]
-    NotConnected,
+    #[error("Unknown column: {0}")]
+    UnknownColumn(String),
+
+    #[error("Not editable: {0}")]
+    NotEditable(String),
+
+    #[error("Nothing to undo")]
+    NothingToUndo,
 
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
@@ -29,6 +62,15 @@ pub enum AppError {
     ConfigError(String),
 }
 
+/// SQLSTATE `query_canceled`, raised by `statement_timeout` and
+/// `pg_cancel_backend`
+const QUERY_CANCELED: &str = "57014";
+
+/// Whether a statement was stopped by a cancel request or a timeout.
+pub fn is_query_canceled(e: &sqlx::Error) -> bool {
+    matches!(e, sqlx::Error::Database(db_err) if db_err.code().as_deref() == Some(QUERY_CANCELED))
+}
+
 impl Serialize for AppError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -37,3 +79,9 @@ impl Serialize for AppError {
         serializer.serialize_str(&self.to_string())
     }
 }
+
+impl From<russh::Error> for AppError {
+    fn from(e: russh::Error) -> Self {
+        AppError::SshTunnel(e.to_string())
+    }
+}
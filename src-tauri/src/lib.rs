@@ -19,9 +19,21 @@ pub fn run() {
             commands::connection::disconnect,
             commands::connection::get_connection_status,
             commands::query::execute_query,
+            commands::query::execute_query_stream,
+            commands::query::execute_script,
+            commands::query::cancel_query,
+            commands::transaction::begin_transaction,
+            commands::transaction::commit_transaction,
+            commands::transaction::rollback_transaction,
+            commands::transaction::get_transaction_status,
             commands::schema::get_schemas,
+            commands::schema::get_schema_names,
+            commands::schema::get_tables,
+            commands::schema::get_columns,
             commands::schema::get_foreign_keys,
             commands::schema::get_table_detail,
+            commands::schema::get_view_definition,
+            commands::schema::refresh_schema,
             commands::export::export_csv,
             commands::ai::generate_sql,
             commands::settings::save_settings,
@@ -33,12 +45,18 @@ pub fn run() {
             commands::connections::delete_connection,
             commands::connections::set_default_connection,
             commands::connections::get_connection_password,
+            commands::connections::get_ssh_secret,
             commands::connections::get_default_connection,
             commands::table_data::get_table_data,
             commands::table_data::get_table_row_count,
+            commands::table_data::count_table_rows,
             commands::table_data::insert_rows,
             commands::table_data::update_rows,
             commands::table_data::delete_rows,
+            commands::table_data::apply_changes,
+            commands::table_data::preview_changes,
+            commands::table_data::undo_last_change,
+            commands::table_data::list_changes,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -54,16 +72,25 @@ mod tests {
         // Connection types
         ConnectionConfig::export_all().unwrap();
         SslMode::export_all().unwrap();
+        SshAuthMethod::export_all().unwrap();
+        SshTunnelConfig::export_all().unwrap();
         SavedConnection::export_all().unwrap();
         SaveConnectionInput::export_all().unwrap();
+        ActiveConnection::export_all().unwrap();
 
         // Query types
         ColumnMetadata::export_all().unwrap();
         QueryResult::export_all().unwrap();
+        QueryStreamEvent::export_all().unwrap();
         QueryHistoryItem::export_all().unwrap();
+        TransactionStatus::export_all().unwrap();
+        ScriptOptions::export_all().unwrap();
+        StatementOutcome::export_all().unwrap();
+        StatementResult::export_all().unwrap();
 
         // Schema types
         ColumnInfo::export_all().unwrap();
+        RelationKind::export_all().unwrap();
         TableInfo::export_all().unwrap();
         SchemaInfo::export_all().unwrap();
         ForeignKeyInfo::export_all().unwrap();
@@ -73,12 +100,27 @@ mod tests {
 
         // Table data types
         TableDataRequest::export_all().unwrap();
+        TableFilter::export_all().unwrap();
+        FilterOperator::export_all().unwrap();
+        FilterCombinator::export_all().unwrap();
+        SortKey::export_all().unwrap();
+        SortDirection::export_all().unwrap();
+        NullsOrder::export_all().unwrap();
         TableColumnInfo::export_all().unwrap();
         TableRow::export_all().unwrap();
         TableData::export_all().unwrap();
+        RowCount::export_all().unwrap();
+        RowKeyKind::export_all().unwrap();
         RowUpdate::export_all().unwrap();
         RowInsert::export_all().unwrap();
         RowDelete::export_all().unwrap();
+        ChangeSet::export_all().unwrap();
+        ChangeKind::export_all().unwrap();
+        ChangeError::export_all().unwrap();
+        ChangeSetResult::export_all().unwrap();
+        ChangePreview::export_all().unwrap();
+        StatementPreview::export_all().unwrap();
+        ChangeRecord::export_all().unwrap();
 
         // AI types
         AiProvider::export_all().unwrap();
@@ -1,16 +1,94 @@
+use crate::db::schema_cache::SchemaCaches;
 use crate::db::DatabaseConnection;
+use crate::error::AppError;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// A statement currently executing on a backend, tracked so it can be
+/// cancelled from another connection.
+pub struct RunningQuery {
+    pub connection_id: String,
+    /// Backend running the query; `None` while it waits for the session
+    pub backend_pid: Option<i32>,
+    pub cancel_requested: bool,
+}
+
 pub struct AppState {
-    pub connection: Mutex<Option<DatabaseConnection>>,
+    /// Open database connections keyed by `ConnectionConfig.id`
+    pub connections: Mutex<HashMap<String, Arc<DatabaseConnection>>>,
+    /// Running queries keyed by query id
+    pub running_queries: Mutex<HashMap<String, RunningQuery>>,
+    /// Cached schema metadata keyed by connection id
+    pub schema_caches: SchemaCaches,
 }
 
 impl AppState {
     pub fn new() -> Self {
         Self {
-            connection: Mutex::new(None),
+            connections: Mutex::new(HashMap::new()),
+            running_queries: Mutex::new(HashMap::new()),
+            schema_caches: Mutex::new(HashMap::new()),
         }
     }
+
+    /// Looks up an open connection by id.
+    ///
+    /// The registry lock is released before returning, so long-running
+    /// work on one connection never blocks commands on another.
+    pub async fn connection(
+        &self,
+        connection_id: &str,
+    ) -> Result<Arc<DatabaseConnection>, AppError> {
+        let connections = self.connections.lock().await;
+        connections
+            .get(connection_id)
+            .cloned()
+            .ok_or_else(|| AppError::ConnectionNotFound(connection_id.to_string()))
+    }
+
+    /// Registers a query before it waits for a backend, so it can be
+    /// cancelled while it waits.
+    pub async fn register_query(&self, query_id: &str, connection_id: &str) {
+        let mut running = self.running_queries.lock().await;
+        running.insert(
+            query_id.to_string(),
+            RunningQuery {
+                connection_id: connection_id.to_string(),
+                backend_pid: None,
+                cancel_requested: false,
+            },
+        );
+    }
+
+    /// Records the backend a registered query runs on. Returns false if the
+    /// query was cancelled while it waited, in which case it must not run.
+    pub async fn start_query(&self, query_id: &str, backend_pid: i32) -> bool {
+        let mut running = self.running_queries.lock().await;
+        match running.get_mut(query_id) {
+            Some(query) if !query.cancel_requested => {
+                query.backend_pid = Some(backend_pid);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub async fn is_cancel_requested(&self, query_id: &str) -> bool {
+        let running = self.running_queries.lock().await;
+        running.get(query_id).is_some_and(|q| q.cancel_requested)
+    }
+
+    /// Drops the cached schema metadata of a connection.
+    pub async fn invalidate_schema(&self, connection_id: &str) {
+        self.schema_caches.lock().await.remove(connection_id);
+    }
+
+    /// Removes a finished query and returns its entry.
+    pub async fn finish_query(&self, query_id: &str) -> Option<RunningQuery> {
+        let mut running = self.running_queries.lock().await;
+        running.remove(query_id)
+    }
 }
 
 impl Default for AppState {
@@ -18,3 +96,34 @@ impl Default for AppState {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_query_cancelled_while_waiting() {
+        let state = AppState::new();
+
+        state.register_query("q1", "c").await;
+        assert!(state.start_query("q1", 42).await);
+        assert_eq!(
+            state.finish_query("q1").await.unwrap().backend_pid,
+            Some(42)
+        );
+
+        // A cancel that arrives before the query has a backend stops it
+        // from starting
+        state.register_query("q2", "c").await;
+        state
+            .running_queries
+            .lock()
+            .await
+            .get_mut("q2")
+            .unwrap()
+            .cancel_requested = true;
+        assert!(!state.start_query("q2", 42).await);
+
+        assert!(!state.start_query("unknown", 42).await);
+    }
+}
@@ -5,12 +5,46 @@ use ts_rs::TS;
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../src/types/generated/")]
-#[serde(rename_all = "lowercase")]
+#[serde(rename_all = "kebab-case")]
 pub enum SslMode {
     #[default]
     Disable,
+    Allow,
     Prefer,
     Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum SshAuthMethod {
+    #[default]
+    Password,
+    PrivateKey,
+}
+
+/// SSH jump host used to reach a database that is not directly exposed.
+///
+/// Secrets are never serialized; they live in the keyring next to the
+/// database password.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct SshTunnelConfig {
+    pub host: String,
+    pub port: u16,
+    pub username: String,
+    #[serde(default)]
+    pub auth_method: SshAuthMethod,
+    #[ts(optional)]
+    pub private_key_path: Option<String>,
+    #[serde(default, skip_serializing)]
+    #[ts(skip)]
+    pub password: String,
+    #[serde(default, skip_serializing)]
+    #[ts(skip)]
+    pub passphrase: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -27,6 +61,21 @@ pub struct ConnectionConfig {
     pub password: String,
     #[serde(default)]
     pub ssl_mode: SslMode,
+    /// Path to the root CA certificate (libpq `sslrootcert`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_root_cert: Option<String>,
+    /// Path to the client certificate (libpq `sslcert`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_cert: Option<String>,
+    /// Path to the client certificate's private key (libpq `sslkey`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_key: Option<String>,
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssh: Option<SshTunnelConfig>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -39,6 +88,21 @@ pub struct SavedConnection {
     pub database: String,
     pub username: String,
     pub ssl_mode: SslMode,
+    /// Path to the root CA certificate (libpq `sslrootcert`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_root_cert: Option<String>,
+    /// Path to the client certificate (libpq `sslcert`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_cert: Option<String>,
+    /// Path to the client certificate's private key (libpq `sslkey`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_key: Option<String>,
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssh: Option<SshTunnelConfig>,
     pub is_default: bool,
 }
 
@@ -53,5 +117,31 @@ pub struct SaveConnectionInput {
     pub username: String,
     pub password: String,
     pub ssl_mode: SslMode,
+    /// Path to the root CA certificate (libpq `sslrootcert`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_root_cert: Option<String>,
+    /// Path to the client certificate (libpq `sslcert`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_cert: Option<String>,
+    /// Path to the client certificate's private key (libpq `sslkey`)
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssl_key: Option<String>,
+    #[serde(default)]
+    #[ts(optional)]
+    pub ssh: Option<SshTunnelConfig>,
     pub is_default: bool,
 }
+
+/// An open database session, as listed by `get_connection_status`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct ActiveConnection {
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub database: String,
+}
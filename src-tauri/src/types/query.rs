@@ -8,6 +8,13 @@ use ts_rs::TS;
 pub struct ColumnMetadata {
     pub name: String,
     pub data_type: String,
+    /// PostgreSQL type OID
+    #[ts(optional)]
+    pub type_oid: Option<u32>,
+    /// Whether the column may contain NULL, known only for columns read
+    /// directly from a table
+    #[ts(optional)]
+    pub nullable: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -17,6 +24,92 @@ pub struct QueryResult {
     #[ts(type = "unknown[][]")]
     pub rows: Vec<Vec<serde_json::Value>>,
     pub row_count: usize,
+    /// Rows inserted, updated or deleted; equals `row_count` for a `SELECT`
+    #[ts(type = "number")]
+    pub rows_affected: u64,
+    /// Command tag such as `UPDATE 42` or `CREATE TABLE`
+    #[ts(optional)]
+    pub command_tag: Option<String>,
+    #[ts(type = "number")]
+    pub execution_time_ms: u64,
+}
+
+/// Messages sent over the channel of `execute_query_stream`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum QueryStreamEvent {
+    Columns {
+        columns: Vec<ColumnMetadata>,
+    },
+    Rows {
+        #[ts(type = "unknown[][]")]
+        rows: Vec<Vec<serde_json::Value>>,
+        /// Total rows sent so far, including this batch
+        rows_sent: usize,
+    },
+    Finished {
+        row_count: usize,
+        #[ts(type = "number")]
+        rows_affected: u64,
+        #[ts(optional)]
+        command_tag: Option<String>,
+        #[ts(type = "number")]
+        execution_time_ms: u64,
+    },
+}
+
+/// Transaction state of a connection's SQL editor session
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    Idle,
+    InTransaction,
+    /// A statement failed; only `ROLLBACK` is accepted until the
+    /// transaction ends
+    Failed,
+    /// The last probe failed, e.g. because the connection was lost
+    Unknown,
+}
+
+/// Options for `execute_script`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct ScriptOptions {
+    /// Skip the remaining statements after the first error
+    pub stop_on_error: bool,
+    /// Run the whole script in a single transaction. With `stop_on_error`
+    /// an error rolls back every statement; otherwise only the failing
+    /// statement is rolled back and the rest is committed.
+    pub use_transaction: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StatementOutcome {
+    ResultSet {
+        result: QueryResult,
+    },
+    RowsAffected {
+        #[ts(type = "number")]
+        rows_affected: u64,
+        #[ts(optional)]
+        command_tag: Option<String>,
+    },
+    Error {
+        message: String,
+    },
+    /// Not executed because an earlier statement failed
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct StatementResult {
+    pub statement: String,
+    pub outcome: StatementOutcome,
     #[ts(type = "number")]
     pub execution_time_ms: u64,
 }
@@ -30,6 +123,10 @@ pub struct QueryHistoryItem {
     #[ts(optional)]
     pub row_count: Option<usize>,
     #[ts(optional, type = "number")]
+    pub rows_affected: Option<u64>,
+    #[ts(optional)]
+    pub command_tag: Option<String>,
+    #[ts(optional, type = "number")]
     pub execution_time_ms: Option<u64>,
     #[ts(optional)]
     pub error: Option<String>,
@@ -14,11 +14,51 @@ pub struct ColumnInfo {
     pub default_value: Option<String>,
 }
 
+/// Kind of relation listed in a schema, from `pg_class.relkind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum RelationKind {
+    Table,
+    /// Parent of a declaratively partitioned table; holds no rows itself
+    PartitionedTable,
+    View,
+    MaterializedView,
+    ForeignTable,
+}
+
+impl RelationKind {
+    /// Maps a `pg_class.relkind` code, or `None` for relations that aren't
+    /// browsed (indexes, sequences, composite types, ...).
+    pub fn from_relkind(relkind: &str) -> Option<Self> {
+        match relkind {
+            "r" => Some(Self::Table),
+            "p" => Some(Self::PartitionedTable),
+            "v" => Some(Self::View),
+            "m" => Some(Self::MaterializedView),
+            "f" => Some(Self::ForeignTable),
+            _ => None,
+        }
+    }
+
+    /// Whether rows can be written, so the table editor may offer edits
+    pub fn is_writable(self) -> bool {
+        matches!(self, Self::Table | Self::PartitionedTable)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../src/types/generated/")]
 pub struct TableInfo {
     pub schema: String,
     pub name: String,
+    pub kind: RelationKind,
+    /// Schema of the partitioned table this is a partition of
+    #[ts(optional)]
+    pub parent_schema: Option<String>,
+    /// Name of the partitioned table this is a partition of
+    #[ts(optional)]
+    pub parent_table: Option<String>,
     pub columns: Vec<ColumnInfo>,
 }
 
@@ -65,6 +105,7 @@ pub struct ConstraintInfo {
 pub struct TableDetailInfo {
     pub schema: String,
     pub name: String,
+    pub kind: RelationKind,
     pub columns: Vec<ColumnInfo>,
     pub indexes: Vec<IndexInfo>,
     pub constraints: Vec<ConstraintInfo>,
@@ -1,5 +1,6 @@
 //! Table data CRUD types
 
+use super::RelationKind;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use ts_rs::TS;
@@ -11,6 +12,95 @@ pub struct TableDataRequest {
     pub table: String,
     pub limit: usize,
     pub offset: usize,
+    /// Only return rows matching this filter; also applies to `total_count`
+    #[ts(optional)]
+    #[serde(default)]
+    pub filter: Option<TableFilter>,
+    /// Sort keys in priority order; the table's primary key or unique key is
+    /// appended as a tie-breaker, and is the whole order when this is empty
+    #[ts(as = "Option<Vec<SortKey>>", optional)]
+    #[serde(default)]
+    pub sort: Vec<SortKey>,
+    /// Cursor from a previous page's `next_cursor` or `prev_cursor`; when
+    /// set, `offset` is ignored and rows are found by seeking on the sort key
+    #[ts(optional)]
+    #[serde(default)]
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct SortKey {
+    pub column: String,
+    #[ts(as = "Option<SortDirection>", optional)]
+    #[serde(default)]
+    pub direction: SortDirection,
+    /// Where NULLs go; PostgreSQL's default puts them last when ascending
+    /// and first when descending
+    #[ts(optional)]
+    #[serde(default)]
+    pub nulls: Option<NullsOrder>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum NullsOrder {
+    First,
+    Last,
+}
+
+/// Row filter for table data, compiled into a parameterized `WHERE` clause
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TableFilter {
+    Condition {
+        column: String,
+        operator: FilterOperator,
+        /// A single value, an array for `in`, or `[low, high]` for
+        /// `between`; ignored by `is_null`
+        #[ts(type = "unknown")]
+        #[serde(default)]
+        value: serde_json::Value,
+    },
+    Group {
+        combinator: FilterCombinator,
+        filters: Vec<TableFilter>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOperator {
+    Eq,
+    NotEq,
+    Lt,
+    Gt,
+    Like,
+    #[serde(rename = "ilike")]
+    ILike,
+    In,
+    IsNull,
+    Between,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum FilterCombinator {
+    And,
+    Or,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -18,6 +108,8 @@ pub struct TableDataRequest {
 pub struct TableColumnInfo {
     pub name: String,
     pub data_type: String,
+    /// Type name usable in a cast, e.g. `numeric`, `public.mood` or `text[]`
+    pub sql_type: String,
     pub is_nullable: bool,
     pub is_primary_key: bool,
     pub is_auto_generated: bool,
@@ -25,12 +117,15 @@ pub struct TableColumnInfo {
     pub default_value: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../src/types/generated/")]
 pub struct TableRow {
     pub id: String,
     #[ts(type = "unknown[]")]
     pub values: Vec<serde_json::Value>,
+    /// Row version (`xmin`) when loaded, for optimistic concurrency checks
+    #[ts(optional)]
+    pub xmin: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -39,8 +134,44 @@ pub struct TableData {
     pub columns: Vec<TableColumnInfo>,
     pub rows: Vec<TableRow>,
     pub total_count: usize,
+    /// True if `total_count` is the planner's estimate; pages are loaded
+    /// with one, and an exact count is asked for separately
+    pub is_count_estimated: bool,
     pub primary_keys: Vec<String>,
     pub has_primary_key: bool,
+    /// Kind of relation browsed; only tables can be edited
+    pub kind: RelationKind,
+    /// How rows are identified for editing; `None` if they can't be edited
+    #[ts(optional)]
+    pub row_key: Option<RowKeyKind>,
+    /// Cursor for the following page; `None` on the last page or when the
+    /// table has no unique key to seek on
+    #[ts(optional)]
+    pub next_cursor: Option<String>,
+    /// Cursor for the preceding page; `None` on the first page or when the
+    /// table has no unique key to seek on
+    #[ts(optional)]
+    pub prev_cursor: Option<String>,
+}
+
+/// Number of rows in a table, possibly estimated
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct RowCount {
+    pub count: usize,
+    pub is_estimated: bool,
+}
+
+/// Strategy used to identify rows, recorded in each `TableRow.id`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum RowKeyKind {
+    PrimaryKey,
+    /// A unique index over `NOT NULL` columns
+    UniqueIndex,
+    /// The row's physical location plus a match on every column
+    Ctid,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -50,6 +181,15 @@ pub struct RowUpdate {
     pub column: String,
     #[ts(type = "unknown")]
     pub new_value: serde_json::Value,
+    /// `TableRow.xmin` as loaded; the update conflicts if the row has been
+    /// written since
+    #[ts(optional)]
+    #[serde(default)]
+    pub xmin: Option<String>,
+    /// Column values as loaded; the update conflicts if any of them differ
+    #[ts(optional, type = "Record<string, unknown>")]
+    #[serde(default)]
+    pub original_values: Option<HashMap<String, serde_json::Value>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -63,4 +203,103 @@ pub struct RowInsert {
 #[ts(export, export_to = "../../src/types/generated/")]
 pub struct RowDelete {
     pub row_id: String,
+    /// `TableRow.xmin` as loaded; the delete conflicts if the row has been
+    /// written since
+    #[ts(optional)]
+    #[serde(default)]
+    pub xmin: Option<String>,
+    /// Column values as loaded; the delete conflicts if any of them differ
+    #[ts(optional, type = "Record<string, unknown>")]
+    #[serde(default)]
+    pub original_values: Option<HashMap<String, serde_json::Value>>,
+}
+
+/// Grid edits for one table, applied together in a single transaction
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct ChangeSet {
+    pub schema: String,
+    pub table: String,
+    #[serde(default)]
+    pub inserts: Vec<RowInsert>,
+    #[serde(default)]
+    pub updates: Vec<RowUpdate>,
+    #[serde(default)]
+    pub deletes: Vec<RowDelete>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Insert,
+    Update,
+    Delete,
+}
+
+/// A failed edit, identified by its kind and position in the change set
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct ChangeError {
+    pub kind: ChangeKind,
+    pub index: usize,
+    #[ts(optional)]
+    pub row_id: Option<String>,
+    /// The column whose value was rejected, if the error is about a value
+    #[ts(optional)]
+    pub column: Option<String>,
+    pub message: String,
+    /// The row as it is now, when the edit conflicts with a concurrent change
+    #[ts(optional)]
+    pub current_row: Option<TableRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct ChangeSetResult {
+    /// False if any edit failed, in which case none of them were applied
+    pub committed: bool,
+    pub inserted: usize,
+    pub updated: usize,
+    pub deleted: usize,
+    /// Inserted rows as stored, with generated values and their row ids
+    pub inserted_rows: Vec<TableRow>,
+    pub errors: Vec<ChangeError>,
+}
+
+/// The statements a change set would run, in order, without running them
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct ChangePreview {
+    pub statements: Vec<StatementPreview>,
+    /// Every statement with literals inlined, one per line, ending in `;`
+    pub script: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct StatementPreview {
+    pub kind: ChangeKind,
+    /// Indexes of the edits the statement applies; an insert may cover
+    /// several rows
+    pub indexes: Vec<usize>,
+    /// The statement with its values inlined as quoted literals
+    pub sql: String,
+    /// The statement as run, with `$n` placeholders
+    pub parameterized_sql: String,
+    /// Text of each placeholder's value, `null` for NULL
+    pub params: Vec<Option<String>>,
+}
+
+/// An applied edit that can be undone
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../src/types/generated/")]
+pub struct ChangeRecord {
+    pub id: u32,
+    pub schema: String,
+    pub table: String,
+    pub applied_at: String,
+    pub inserted: usize,
+    pub updated: usize,
+    pub deleted: usize,
 }
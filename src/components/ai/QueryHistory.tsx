@@ -89,9 +89,14 @@ export function QueryHistory() {
                           <Clock className="h-2.5 w-2.5" />
                           {formatTime(item.executedAt)}
                         </span>
-                        {!item.error && item.rowCount !== undefined && (
-                          <span>{item.rowCount} rows</span>
+                        {!item.error && item.commandTag !== undefined && (
+                          <span>{item.commandTag}</span>
                         )}
+                        {!item.error &&
+                          item.commandTag === undefined &&
+                          item.rowCount !== undefined && (
+                            <span>{item.rowCount} rows</span>
+                          )}
                         {!item.error && item.executionTimeMs !== undefined && (
                           <span>{item.executionTimeMs}ms</span>
                         )}
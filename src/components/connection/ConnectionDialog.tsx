@@ -1,6 +1,6 @@
 import { useState, useEffect } from "react";
 import { Trash2, Star, Plug } from "lucide-react";
-import type { ConnectionConfig } from "../../types/connection";
+import type { ConnectionConfig, SslMode } from "../../types/connection";
 import { useConnectionStore } from "../../store/connectionStore";
 import { Dialog, DialogContent, DialogHeader, DialogTitle } from "../ui/dialog";
 import { Button } from "../ui/button";
@@ -101,6 +101,9 @@ export function ConnectionDialog({ isOpen, onClose }: ConnectionDialogProps) {
           username: saved.username,
           password,
           ssl_mode: saved.ssl_mode,
+          ssl_root_cert: saved.ssl_root_cert,
+          ssl_cert: saved.ssl_cert,
+          ssl_key: saved.ssl_key,
         });
         setIsDefault(saved.is_default);
       } catch {
@@ -113,6 +116,9 @@ export function ConnectionDialog({ isOpen, onClose }: ConnectionDialogProps) {
           username: saved.username,
           password: "",
           ssl_mode: saved.ssl_mode,
+          ssl_root_cert: saved.ssl_root_cert,
+          ssl_cert: saved.ssl_cert,
+          ssl_key: saved.ssl_key,
         });
         setIsDefault(saved.is_default);
       }
@@ -124,7 +130,7 @@ export function ConnectionDialog({ isOpen, onClose }: ConnectionDialogProps) {
   const handleSslModeChange = (value: string) => {
     setForm((prev) => ({
       ...prev,
-      ssl_mode: value as "disable" | "prefer" | "require",
+      ssl_mode: value as SslMode,
     }));
     setTestResult(null);
     clearError();
@@ -317,12 +323,44 @@ export function ConnectionDialog({ isOpen, onClose }: ConnectionDialogProps) {
               </SelectTrigger>
               <SelectContent>
                 <SelectItem value="disable">Disable</SelectItem>
+                <SelectItem value="allow">Allow</SelectItem>
                 <SelectItem value="prefer">Prefer</SelectItem>
                 <SelectItem value="require">Require</SelectItem>
+                <SelectItem value="verify-ca">Verify CA</SelectItem>
+                <SelectItem value="verify-full">Verify Full</SelectItem>
               </SelectContent>
             </Select>
           </div>
 
+          {form.ssl_mode !== "disable" && (
+            <div className="space-y-1.5">
+              <label className="text-[13px] font-medium">
+                Certificates (optional)
+              </label>
+              <Input
+                type="text"
+                name="ssl_root_cert"
+                value={form.ssl_root_cert ?? ""}
+                onChange={handleChange}
+                placeholder="Root CA certificate path"
+              />
+              <Input
+                type="text"
+                name="ssl_cert"
+                value={form.ssl_cert ?? ""}
+                onChange={handleChange}
+                placeholder="Client certificate path"
+              />
+              <Input
+                type="text"
+                name="ssl_key"
+                value={form.ssl_key ?? ""}
+                onChange={handleChange}
+                placeholder="Client key path"
+              />
+            </div>
+          )}
+
           {/* Default checkbox */}
           <div className="flex items-center gap-2">
             <input
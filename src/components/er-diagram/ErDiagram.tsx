@@ -123,9 +123,14 @@ function applyDagreLayout(
 }
 
 export function ErDiagram() {
-  const { schemas, focusedTable, setFocusedTable, clearFocusedTable } =
-    useSchemaStore();
-  const { isConnected } = useConnectionStore();
+  const {
+    schemas,
+    focusedTable,
+    setFocusedTable,
+    clearFocusedTable,
+    fetchSchemaDetails,
+  } = useSchemaStore();
+  const { isConnected, connectionId } = useConnectionStore();
   const { isDark } = useTheme();
   const [foreignKeys, setForeignKeys] = useState<ForeignKeyInfo[]>([]);
   const [nodes, setNodes, onNodesChange] = useNodesState<TableNodeType>([]);
@@ -136,16 +141,25 @@ export function ErDiagram() {
   const edgeColor = isDark ? "#60a5fa" : "#3b82f6";
   const bgColor = isDark ? "#374151" : "#e5e7eb";
 
+  // The diagram needs every table of the schema with its columns. Waits
+  // for the schema list, and loads again after the list is reloaded.
+  useEffect(() => {
+    if (isConnected && schemas.some((s) => s.name === selectedSchema)) {
+      fetchSchemaDetails([selectedSchema]);
+    }
+  }, [isConnected, schemas, selectedSchema, fetchSchemaDetails]);
+
   // Fetch foreign keys when schema changes
   useEffect(() => {
-    if (isConnected && selectedSchema) {
+    if (isConnected && connectionId && selectedSchema) {
       invoke<ForeignKeyInfo[]>("get_foreign_keys", {
+        connectionId,
         schemaName: selectedSchema,
       })
         .then(setForeignKeys)
         .catch(console.error);
     }
-  }, [isConnected, selectedSchema]);
+  }, [isConnected, connectionId, selectedSchema]);
 
   // Get tables for selected schema
   const tables = useMemo(() => {
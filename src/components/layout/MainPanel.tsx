@@ -1,7 +1,7 @@
 import { useState, useCallback, useEffect, useRef } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import { save } from "@tauri-apps/plugin-dialog";
-import { Play, Download, X, FileSpreadsheet } from "lucide-react";
+import { Play, Square, Download, X, FileSpreadsheet } from "lucide-react";
 import { SqlEditor } from "../editor";
 import { ResultGrid } from "../result";
 import { ErDiagram } from "../er-diagram";
@@ -29,6 +29,7 @@ export function MainPanel() {
   const [isExporting, setIsExporting] = useState(false);
   const {
     executeQuery,
+    cancelQuery,
     query,
     isExecuting,
     result,
@@ -36,6 +37,10 @@ export function MainPanel() {
     currentSchema,
     currentTable,
     exitCrudMode,
+    transactionStatus,
+    beginTransaction,
+    commitTransaction,
+    rollbackTransaction,
   } = useQueryStore();
   const { isConnected } = useConnectionStore();
 
@@ -125,6 +130,36 @@ export function MainPanel() {
 
           {/* Query buttons */}
           <div className="flex items-center gap-2 pr-2">
+            {transactionStatus === "idle" ? (
+              <Button
+                variant="ghost"
+                size="xs"
+                onClick={beginTransaction}
+                disabled={!isConnected || isExecuting}
+              >
+                Begin
+              </Button>
+            ) : (
+              <>
+                <Button
+                  variant="outline"
+                  size="xs"
+                  onClick={commitTransaction}
+                  disabled={isExecuting || transactionStatus === "failed"}
+                >
+                  Commit
+                </Button>
+                <Button
+                  variant="outline"
+                  size="xs"
+                  onClick={rollbackTransaction}
+                  disabled={isExecuting}
+                >
+                  Rollback
+                </Button>
+              </>
+            )}
+
             <DropdownMenu>
               <Tooltip>
                 <TooltipTrigger asChild>
@@ -148,19 +183,34 @@ export function MainPanel() {
               </DropdownMenuContent>
             </DropdownMenu>
 
-            <Tooltip>
-              <TooltipTrigger asChild>
-                <Button
-                  variant="success"
-                  size="icon-sm"
-                  onClick={executeQuery}
-                  disabled={!isConnected || !query.trim() || isExecuting}
-                >
-                  <Play className="h-4 w-4" />
-                </Button>
-              </TooltipTrigger>
-              <TooltipContent>Run Query</TooltipContent>
-            </Tooltip>
+            {isExecuting ? (
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="destructive"
+                    size="icon-sm"
+                    onClick={cancelQuery}
+                  >
+                    <Square className="h-4 w-4" />
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>Cancel Query</TooltipContent>
+              </Tooltip>
+            ) : (
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="success"
+                    size="icon-sm"
+                    onClick={executeQuery}
+                    disabled={!isConnected || !query.trim()}
+                  >
+                    <Play className="h-4 w-4" />
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>Run Query</TooltipContent>
+              </Tooltip>
+            )}
           </div>
         </div>
 
@@ -1,5 +1,8 @@
+import { RefreshCw } from "lucide-react";
 import { SchemaTree } from "../schema";
 import { useQueryStore } from "../../store/queryStore";
+import { useSchemaStore } from "../../store/schemaStore";
+import { useConnectionStore } from "../../store/connectionStore";
 import { ScrollArea } from "../ui/scroll-area";
 
 interface SidebarProps {
@@ -8,6 +11,8 @@ interface SidebarProps {
 
 export function Sidebar({ width = 240 }: SidebarProps) {
   const loadTableData = useQueryStore((state) => state.loadTableData);
+  const refreshSchemas = useSchemaStore((state) => state.refreshSchemas);
+  const isConnected = useConnectionStore((state) => state.isConnected);
 
   const handleTableSelect = (schemaName: string, tableName: string) => {
     // Double-click opens table in CRUD mode
@@ -19,10 +24,19 @@ export function Sidebar({ width = 240 }: SidebarProps) {
       className="flex flex-col border-r border-[hsl(var(--sidebar-border))] bg-[hsl(var(--sidebar))]"
       style={{ width }}
     >
-      <div className="border-b border-[hsl(var(--sidebar-border))] px-3 py-2">
+      <div className="flex items-center justify-between border-b border-[hsl(var(--sidebar-border))] px-3 py-2">
         <div className="text-[11px] font-semibold uppercase tracking-wider text-[hsl(var(--muted-foreground))]">
           Schema
         </div>
+        {isConnected && (
+          <button
+            onClick={() => refreshSchemas()}
+            className="rounded-[var(--radius-sm)] p-0.5 text-[hsl(var(--muted-foreground))] hover:bg-[hsl(var(--accent))] transition-colors"
+            title="Refresh schema"
+          >
+            <RefreshCw className="h-3.5 w-3.5" />
+          </button>
+        )}
       </div>
       <ScrollArea className="flex-1">
         <SchemaTree onTableSelect={handleTableSelect} />
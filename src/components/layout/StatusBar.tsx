@@ -1,8 +1,10 @@
 import { useConnectionStore } from "../../store/connectionStore";
+import { useQueryStore } from "../../store/queryStore";
 import { cn } from "../../lib/utils";
 
 export function StatusBar() {
   const { isConnected, connectionName } = useConnectionStore();
+  const { transactionStatus } = useQueryStore();
 
   return (
     <footer className="flex h-6 items-center justify-between border-t border-[hsl(var(--border))] bg-[hsl(var(--muted))] px-3 text-[11px]">
@@ -18,6 +20,22 @@ export function StatusBar() {
         <span className="text-[hsl(var(--muted-foreground))]">
           {isConnected ? `Connected to ${connectionName}` : "Not connected"}
         </span>
+        {isConnected && transactionStatus !== "idle" && (
+          <span
+            className={cn(
+              "rounded-[var(--radius-sm)] px-1.5 font-medium",
+              transactionStatus === "failed"
+                ? "bg-[hsl(var(--destructive))]/15 text-[hsl(var(--destructive))]"
+                : "bg-[hsl(var(--warning))]/15 text-[hsl(var(--warning))]"
+            )}
+          >
+            {transactionStatus === "failed"
+              ? "Transaction failed"
+              : transactionStatus === "unknown"
+                ? "Transaction state unknown"
+                : "In transaction"}
+          </span>
+        )}
       </div>
       <div className="text-[hsl(var(--muted-foreground))]">v0.1.0</div>
     </footer>
@@ -0,0 +1,60 @@
+import { useState } from "react";
+import { Copy } from "lucide-react";
+import type { ChangePreview } from "../../types/query";
+import { Dialog, DialogContent, DialogHeader, DialogTitle } from "../ui/dialog";
+import { Button } from "../ui/button";
+
+interface ChangePreviewDialogProps {
+  preview: ChangePreview | null;
+  onClose: () => void;
+}
+
+export function ChangePreviewDialog({
+  preview,
+  onClose,
+}: ChangePreviewDialogProps) {
+  const [parameterized, setParameterized] = useState(false);
+
+  const text = !preview
+    ? ""
+    : parameterized
+      ? preview.statements
+          .map(
+            (s) =>
+              `${s.parameterized_sql};\n-- params: ${JSON.stringify(s.params)}\n`,
+          )
+          .join("")
+      : preview.script;
+
+  return (
+    <Dialog open={!!preview} onOpenChange={(open) => !open && onClose()}>
+      <DialogContent className="sm:max-w-3xl">
+        <DialogHeader>
+          <DialogTitle>Pending Changes SQL</DialogTitle>
+        </DialogHeader>
+        <div className="flex items-center justify-between">
+          <label className="flex items-center gap-2 text-xs">
+            <input
+              type="checkbox"
+              checked={parameterized}
+              onChange={(e) => setParameterized(e.target.checked)}
+              className="h-4 w-4 rounded border-[hsl(var(--border))]"
+            />
+            Show parameterized form
+          </label>
+          <Button
+            variant="ghost"
+            size="sm"
+            onClick={() => navigator.clipboard.writeText(text)}
+          >
+            <Copy className="h-4 w-4" />
+            Copy
+          </Button>
+        </div>
+        <pre className="max-h-[60vh] overflow-auto whitespace-pre-wrap break-all rounded-sm bg-[hsl(var(--muted))] p-3 font-[var(--font-mono)] text-xs">
+          {text}
+        </pre>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -21,11 +21,20 @@ import {
   Key,
   ChevronLeft,
   ChevronRight,
+  FileCode,
+  Undo2,
 } from "lucide-react";
-import type { TableData, TableRow, TableColumnInfo } from "../../types/query";
+import type {
+  TableData,
+  TableRow,
+  TableColumnInfo,
+  ChangePreview,
+} from "../../types/query";
+import { RELATION_KIND_LABELS, isWritableKind } from "../../types/schema";
 import { useQueryStore } from "../../store/queryStore";
 import { EditableCell } from "./EditableCell";
 import { InsertRowDialog } from "./InsertRowDialog";
+import { ChangePreviewDialog } from "./ChangePreviewDialog";
 import { Button } from "../ui/button";
 import {
   Tooltip,
@@ -58,7 +67,12 @@ export function CrudTable({
     updateCell,
     setPage,
     setPageSize,
+    countQueryId,
+    countRowsExactly,
+    cancelRowCount,
     saveChanges,
+    previewChanges,
+    undoLastChange,
     discardChanges,
     refreshTableData,
     markForDelete,
@@ -68,8 +82,14 @@ export function CrudTable({
   const [sorting, setSorting] = useState<SortingState>([]);
   const [rowSelection, setRowSelection] = useState<RowSelectionState>({});
   const [isInsertDialogOpen, setIsInsertDialogOpen] = useState(false);
+  const [preview, setPreview] = useState<ChangePreview | null>(null);
 
-  const isReadOnly = !tableData.has_primary_key;
+  const isReadOnly = !tableData.row_key;
+  // An estimated count can't tell where the last page is, so a short page
+  // marks the end instead
+  const isLastPage = tableData.is_count_estimated
+    ? tableData.rows.length < pageSize
+    : (currentPage + 1) * pageSize >= tableData.total_count;
   const hasPendingChanges =
     pendingChanges.size > 0 ||
     pendingInserts.length > 0 ||
@@ -196,7 +216,9 @@ export function CrudTable({
       <div className="flex h-full flex-col overflow-hidden">
         {isReadOnly && (
           <div className="bg-[hsl(var(--warning))]/10 px-3 py-1 text-xs text-[hsl(var(--warning))]">
-            This table has no primary key. Data is read-only.
+            {isWritableKind(tableData.kind)
+              ? "Rows of this relation cannot be identified. Data is read-only."
+              : `${RELATION_KIND_LABELS[tableData.kind]} data is read-only.`}
           </div>
         )}
         <div className="flex-1 overflow-auto">
@@ -328,7 +350,7 @@ export function CrudTable({
         {/* Bottom toolbar with CRUD buttons and Pagination */}
         <div className="flex items-center justify-between border-t border-[hsl(var(--border))] bg-[hsl(var(--muted))] px-2 py-1">
           {/* CRUD Toolbar */}
-          {tableData.has_primary_key ? (
+          {!isReadOnly ? (
             <div className="flex items-center gap-0.5">
               <Tooltip>
                 <TooltipTrigger asChild>
@@ -375,6 +397,20 @@ export function CrudTable({
                 <TooltipContent>Save Changes</TooltipContent>
               </Tooltip>
 
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="ghost"
+                    size="icon-sm"
+                    onClick={async () => setPreview(await previewChanges())}
+                    disabled={!hasPendingChanges || isLoading}
+                  >
+                    <FileCode className="h-4 w-4" />
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>Preview SQL</TooltipContent>
+              </Tooltip>
+
               <Tooltip>
                 <TooltipTrigger asChild>
                   <Button
@@ -394,7 +430,21 @@ export function CrudTable({
                   <Button
                     variant="ghost"
                     size="icon-sm"
-                    onClick={refreshTableData}
+                    onClick={undoLastChange}
+                    disabled={hasPendingChanges || isLoading}
+                  >
+                    <Undo2 className="h-4 w-4" />
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>Undo Last Save</TooltipContent>
+              </Tooltip>
+
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="ghost"
+                    size="icon-sm"
+                    onClick={() => refreshTableData()}
                     disabled={isLoading}
                   >
                     <RefreshCw className="h-4 w-4" />
@@ -405,7 +455,9 @@ export function CrudTable({
             </div>
           ) : (
             <span className="text-xs text-[hsl(var(--muted-foreground))]">
-              Read-only (no primary key)
+              {isWritableKind(tableData.kind)
+                ? "Read-only (no row key)"
+                : `Read-only (${RELATION_KIND_LABELS[tableData.kind].toLowerCase()})`}
             </span>
           )}
 
@@ -413,9 +465,28 @@ export function CrudTable({
           <div className="flex items-center gap-2">
             <span className="text-xs text-[hsl(var(--muted-foreground))]">
               {currentPage * pageSize + 1}-
-              {Math.min((currentPage + 1) * pageSize, tableData.total_count)} of{" "}
-              {tableData.total_count}
+              {currentPage * pageSize + tableData.rows.length} of{" "}
+              {tableData.is_count_estimated && "~"}
+              {tableData.total_count.toLocaleString()}
             </span>
+            {tableData.is_count_estimated && (
+              <Tooltip>
+                <TooltipTrigger asChild>
+                  <Button
+                    variant="ghost"
+                    size="xs"
+                    onClick={countQueryId ? cancelRowCount : countRowsExactly}
+                  >
+                    {countQueryId ? "Cancel count" : "Count"}
+                  </Button>
+                </TooltipTrigger>
+                <TooltipContent>
+                  {countQueryId
+                    ? "Stop counting rows"
+                    : "Count rows exactly; may be slow on large tables"}
+                </TooltipContent>
+              </Tooltip>
+            )}
             <select
               value={pageSize}
               onChange={(e) => setPageSize(Number(e.target.value))}
@@ -438,7 +509,7 @@ export function CrudTable({
                 variant="ghost"
                 size="icon-sm"
                 onClick={() => setPage(currentPage + 1)}
-                disabled={(currentPage + 1) * pageSize >= tableData.total_count}
+                disabled={isLastPage}
               >
                 <ChevronRight className="h-4 w-4" />
               </Button>
@@ -456,6 +527,11 @@ export function CrudTable({
             setIsInsertDialogOpen(false);
           }}
         />
+
+        <ChangePreviewDialog
+          preview={preview}
+          onClose={() => setPreview(null)}
+        />
       </div>
     </TooltipProvider>
   );
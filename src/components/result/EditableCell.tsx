@@ -207,6 +207,9 @@ function formatValue(value: unknown): string {
   if (typeof value === "boolean") {
     return value ? "true" : "false";
   }
+  if (typeof value === "object") {
+    return JSON.stringify(value);
+  }
   return String(value);
 }
 
@@ -260,27 +263,16 @@ function parseValue(value: string, dataType: string): unknown {
     return value === "true";
   }
 
-  // Integer types
-  if (
-    lowerType.includes("int") ||
-    lowerType === "smallint" ||
-    lowerType === "bigint"
-  ) {
-    const num = parseInt(value, 10);
-    return isNaN(num) ? value : num;
-  }
-
-  // Float types
-  if (
-    lowerType.includes("float") ||
-    lowerType.includes("double") ||
-    lowerType === "numeric" ||
-    lowerType === "decimal" ||
-    lowerType === "real"
-  ) {
-    const num = parseFloat(value);
-    return isNaN(num) ? value : num;
+  // Arrays are edited as JSON; a PostgreSQL literal like {1,2} also works
+  if (lowerType === "array" && value.startsWith("[")) {
+    try {
+      return JSON.parse(value);
+    } catch {
+      return value;
+    }
   }
 
+  // Everything else is sent as typed; the backend casts it to the column
+  // type, so numerics keep their full precision
   return value;
 }
@@ -57,26 +57,11 @@ export function InsertRowDialog({
         }
       }
 
-      // Parse based on type
+      // Only booleans are parsed; the backend casts other values to the
+      // column type
       const lowerType = col.data_type.toLowerCase();
       if (lowerType === "boolean") {
         parsedValues[col.name] = strValue === "true";
-      } else if (
-        lowerType.includes("int") ||
-        lowerType === "smallint" ||
-        lowerType === "bigint"
-      ) {
-        const num = parseInt(strValue, 10);
-        parsedValues[col.name] = isNaN(num) ? strValue : num;
-      } else if (
-        lowerType.includes("float") ||
-        lowerType.includes("double") ||
-        lowerType === "numeric" ||
-        lowerType === "decimal" ||
-        lowerType === "real"
-      ) {
-        const num = parseFloat(strValue);
-        parsedValues[col.name] = isNaN(num) ? strValue : num;
       } else {
         parsedValues[col.name] = strValue;
       }
@@ -66,7 +66,9 @@ export function ResultGrid() {
   if (result.columns.length === 0) {
     return (
       <div className="flex h-full items-center justify-center text-[hsl(var(--muted-foreground))]">
-        Query executed successfully (no results)
+        {result.command_tag
+          ? `${result.command_tag} (${result.execution_time_ms}ms)`
+          : "Query executed successfully (no results)"}
       </div>
     );
   }
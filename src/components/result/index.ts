@@ -1,3 +1,4 @@
 export { ResultGrid } from "./ResultGrid";
 export { EditableCell } from "./EditableCell";
 export { InsertRowDialog } from "./InsertRowDialog";
+export { ChangePreviewDialog } from "./ChangePreviewDialog";
@@ -1,9 +1,33 @@
 import { useState, useEffect } from "react";
-import { ChevronRight, Folder, Table2, Columns3, Key } from "lucide-react";
-import { useSchemaStore } from "../../store/schemaStore";
+import {
+  ChevronRight,
+  Folder,
+  Table2,
+  Columns3,
+  Key,
+  Eye,
+  Layers,
+  Globe,
+  type LucideIcon,
+} from "lucide-react";
+import { tableKey, useSchemaStore } from "../../store/schemaStore";
 import { useConnectionStore } from "../../store/connectionStore";
 import { cn } from "../../lib/utils";
-import type { SchemaInfo, TableInfo, ColumnInfo } from "../../types/schema";
+import {
+  RELATION_KIND_LABELS,
+  type SchemaInfo,
+  type TableInfo,
+  type ColumnInfo,
+  type RelationKind,
+} from "../../types/schema";
+
+const RELATION_KIND_ICONS: Record<RelationKind, LucideIcon> = {
+  table: Table2,
+  partitioned_table: Table2,
+  view: Eye,
+  materialized_view: Layers,
+  foreign_table: Globe,
+};
 
 interface SchemaTreeProps {
   onTableSelect?: (schemaName: string, tableName: string) => void;
@@ -74,6 +98,25 @@ interface SchemaNodeProps {
 
 function SchemaNode({ schema, onTableSelect }: SchemaNodeProps) {
   const [isExpanded, setIsExpanded] = useState(schema.name === "public");
+  const fetchTables = useSchemaStore((state) => state.fetchTables);
+  const isLoaded = useSchemaStore((state) => !!state.loadedSchemas[schema.name]);
+
+  // Tables are loaded when the schema is expanded, and again after the
+  // schemas are reloaded
+  useEffect(() => {
+    if (isExpanded && !isLoaded) {
+      fetchTables(schema.name);
+    }
+  }, [isExpanded, isLoaded, fetchTables, schema.name]);
+
+  // Partitions are listed under their parent when it's in the same schema
+  const isNested = (table: TableInfo) =>
+    table.parent_schema === schema.name &&
+    schema.tables.some((t) => t.name === table.parent_table);
+  const partitionsOf = (table: TableInfo) =>
+    schema.tables.filter(
+      (t) => t.parent_schema === schema.name && t.parent_table === table.name
+    );
 
   return (
     <div>
@@ -89,19 +132,24 @@ function SchemaNode({ schema, onTableSelect }: SchemaNodeProps) {
         />
         <Folder className="h-4 w-4 text-[hsl(var(--tree-icon-schema))]" />
         <span className="font-medium">{schema.name}</span>
-        <span className="ml-auto text-[11px] text-[hsl(var(--muted-foreground))]">
-          {schema.tables.length}
-        </span>
+        {isLoaded && (
+          <span className="ml-auto text-[11px] text-[hsl(var(--muted-foreground))]">
+            {schema.tables.length}
+          </span>
+        )}
       </button>
       {isExpanded && (
         <div className="ml-3 border-l border-[hsl(var(--border))] pl-2">
-          {schema.tables.map((table) => (
-            <TableNode
-              key={`${schema.name}.${table.name}`}
-              table={table}
-              onTableSelect={onTableSelect}
-            />
-          ))}
+          {schema.tables
+            .filter((table) => !isNested(table))
+            .map((table) => (
+              <TableNode
+                key={`${schema.name}.${table.name}`}
+                table={table}
+                partitionsOf={partitionsOf}
+                onTableSelect={onTableSelect}
+              />
+            ))}
         </div>
       )}
     </div>
@@ -110,12 +158,21 @@ function SchemaNode({ schema, onTableSelect }: SchemaNodeProps) {
 
 interface TableNodeProps {
   table: TableInfo;
+  partitionsOf: (table: TableInfo) => TableInfo[];
   onTableSelect?: (schemaName: string, tableName: string) => void;
 }
 
-function TableNode({ table, onTableSelect }: TableNodeProps) {
+function TableNode({ table, partitionsOf, onTableSelect }: TableNodeProps) {
   const [isExpanded, setIsExpanded] = useState(false);
-  const { focusedTable, setFocusedTable } = useSchemaStore();
+  const { focusedTable, setFocusedTable, fetchColumns } = useSchemaStore();
+  const isLoaded = useSchemaStore(
+    (state) => !!state.loadedTables[tableKey(table.schema, table.name)]
+  );
+  const partitions = partitionsOf(table);
+  const Icon = RELATION_KIND_ICONS[table.kind];
+  const title = table.parent_table
+    ? `Partition of ${table.parent_schema}.${table.parent_table}`
+    : RELATION_KIND_LABELS[table.kind];
 
   const isFocused =
     focusedTable?.schema === table.schema && focusedTable?.table === table.name;
@@ -129,6 +186,12 @@ function TableNode({ table, onTableSelect }: TableNodeProps) {
     setIsExpanded(!isExpanded);
   };
 
+  useEffect(() => {
+    if (isExpanded && !isLoaded) {
+      fetchColumns(table.schema, table.name);
+    }
+  }, [isExpanded, isLoaded, fetchColumns, table.schema, table.name]);
+
   const handleDoubleClick = () => {
     onTableSelect?.(table.schema, table.name);
   };
@@ -144,7 +207,7 @@ function TableNode({ table, onTableSelect }: TableNodeProps) {
             ? "bg-[hsl(var(--table-row-selected))] ring-1 ring-[hsl(var(--primary))]/30"
             : "hover:bg-[hsl(var(--accent))]"
         )}
-        title="Click to focus in ER diagram"
+        title={`${title} - click to focus in ER diagram`}
       >
         <span onClick={handleExpandClick} className="cursor-pointer">
           <ChevronRight
@@ -154,14 +217,26 @@ function TableNode({ table, onTableSelect }: TableNodeProps) {
             )}
           />
         </span>
-        <Table2 className="h-4 w-4 text-[hsl(var(--tree-icon-table))]" />
-        <span>{table.name}</span>
-        <span className="ml-auto text-[11px] text-[hsl(var(--muted-foreground))]">
-          {table.columns.length}
+        <Icon className="h-4 w-4 text-[hsl(var(--tree-icon-table))]" />
+        <span className={cn(table.kind !== "table" && "italic")}>
+          {table.name}
         </span>
+        {isLoaded && (
+          <span className="ml-auto text-[11px] text-[hsl(var(--muted-foreground))]">
+            {table.columns.length}
+          </span>
+        )}
       </button>
       {isExpanded && (
         <div className="ml-3 border-l border-[hsl(var(--border))] pl-2">
+          {partitions.map((partition) => (
+            <TableNode
+              key={`${partition.schema}.${partition.name}`}
+              table={partition}
+              partitionsOf={partitionsOf}
+              onTableSelect={onTableSelect}
+            />
+          ))}
           {table.columns.map((column) => (
             <ColumnNode
               key={`${table.schema}.${table.name}.${column.name}`}
@@ -1,6 +1,7 @@
 import { useEffect, useState } from "react";
 import { invoke } from "@tauri-apps/api/core";
 import type { TableDetailInfo } from "../../types/schema";
+import { useConnectionStore } from "../../store/connectionStore";
 
 interface TableDetailPanelProps {
   schemaName: string;
@@ -13,21 +14,43 @@ export function TableDetailPanel({
   tableName,
   onClose,
 }: TableDetailPanelProps) {
+  const connectionId = useConnectionStore((state) => state.connectionId);
   const [detail, setDetail] = useState<TableDetailInfo | null>(null);
   const [isLoading, setIsLoading] = useState(true);
   const [error, setError] = useState<string | null>(null);
   const [activeTab, setActiveTab] = useState<
-    "columns" | "indexes" | "constraints" | "fk"
+    "columns" | "indexes" | "constraints" | "fk" | "definition"
   >("columns");
+  const [definition, setDefinition] = useState<string | null>(null);
 
   useEffect(() => {
     setIsLoading(true);
     setError(null);
-    invoke<TableDetailInfo>("get_table_detail", { schemaName, tableName })
+    setDefinition(null);
+    setActiveTab((tab) => (tab === "definition" ? "columns" : tab));
+    invoke<TableDetailInfo>("get_table_detail", {
+      connectionId,
+      schemaName,
+      tableName,
+    })
       .then(setDetail)
       .catch((err) => setError(String(err)))
       .finally(() => setIsLoading(false));
-  }, [schemaName, tableName]);
+  }, [connectionId, schemaName, tableName]);
+
+  const isView =
+    detail?.kind === "view" || detail?.kind === "materialized_view";
+
+  useEffect(() => {
+    if (activeTab !== "definition" || !isView || definition !== null) return;
+    invoke<string>("get_view_definition", {
+      connectionId,
+      schemaName,
+      viewName: tableName,
+    })
+      .then(setDefinition)
+      .catch((err) => setDefinition(`-- ${String(err)}`));
+  }, [activeTab, isView, definition, connectionId, schemaName, tableName]);
 
   if (isLoading) {
     return (
@@ -66,7 +89,15 @@ export function TableDetailPanel({
       </div>
 
       <div className="flex border-b border-gray-200 bg-white dark:border-gray-700 dark:bg-gray-800">
-        {(["columns", "indexes", "constraints", "fk"] as const).map((tab) => (
+        {(
+          [
+            "columns",
+            "indexes",
+            "constraints",
+            "fk",
+            ...(isView ? (["definition"] as const) : []),
+          ] as const
+        ).map((tab) => (
           <button
             key={tab}
             onClick={() => setActiveTab(tab)}
@@ -81,6 +112,7 @@ export function TableDetailPanel({
             {tab === "constraints" &&
               `Constraints (${detail.constraints.length})`}
             {tab === "fk" && `Foreign Keys (${detail.foreign_keys.length})`}
+            {tab === "definition" && "Definition"}
           </button>
         ))}
       </div>
@@ -239,6 +271,12 @@ export function TableDetailPanel({
           </table>
         )}
 
+        {activeTab === "definition" && (
+          <pre className="whitespace-pre-wrap break-all rounded bg-gray-50 p-3 font-mono text-xs text-gray-700 dark:bg-gray-800 dark:text-gray-300">
+            {definition ?? "Loading definition..."}
+          </pre>
+        )}
+
         {activeTab === "fk" && (
           <table className="w-full border-collapse text-sm">
             <thead>
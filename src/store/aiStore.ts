@@ -75,6 +75,8 @@ export const useAiStore = create<AiState>()((set, get) => ({
 
   generateSql: async (prompt: string) => {
     const { settings } = get();
+    // The tree loads tables lazily, so make sure every column is known
+    await useSchemaStore.getState().fetchSchemaDetails();
     const schemas = useSchemaStore.getState().schemas;
 
     // Build schema context from current schemas
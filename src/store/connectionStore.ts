@@ -1,6 +1,7 @@
 import { create } from "zustand";
 import { invoke } from "@tauri-apps/api/core";
 import type {
+  ActiveConnection,
   ConnectionConfig,
   SavedConnection,
   SaveConnectionInput,
@@ -8,7 +9,9 @@ import type {
 
 interface ConnectionState {
   isConnected: boolean;
+  connectionId: string | null;
   connectionName: string | null;
+  activeConnections: ActiveConnection[];
   isConnecting: boolean;
   error: string | null;
   savedConnections: SavedConnection[];
@@ -34,7 +37,9 @@ interface ConnectionState {
 
 export const useConnectionStore = create<ConnectionState>((set, get) => ({
   isConnected: false,
+  connectionId: null,
   connectionName: null,
+  activeConnections: [],
   isConnecting: false,
   error: null,
   savedConnections: [],
@@ -43,13 +48,26 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
 
   checkConnectionStatus: async () => {
     try {
-      const connectionName = await invoke<string | null>(
+      const activeConnections = await invoke<ActiveConnection[]>(
         "get_connection_status"
       );
-      if (connectionName) {
-        set({ isConnected: true, connectionName });
+      const current =
+        activeConnections.find((c) => c.id === get().connectionId) ??
+        activeConnections[0];
+      if (current) {
+        set({
+          isConnected: true,
+          connectionId: current.id,
+          connectionName: current.name,
+          activeConnections,
+        });
       } else {
-        set({ isConnected: false, connectionName: null });
+        set({
+          isConnected: false,
+          connectionId: null,
+          connectionName: null,
+          activeConnections,
+        });
       }
     } catch (error) {
       console.error("Failed to check connection status:", error);
@@ -73,9 +91,11 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
       await invoke<string>("connect", { config });
       set({
         isConnected: true,
+        connectionId: config.id,
         connectionName: config.name,
         isConnecting: false,
       });
+      await get().checkConnectionStatus();
     } catch (error) {
       set({ isConnecting: false, error: String(error) });
       throw error;
@@ -84,8 +104,12 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
 
   disconnect: async () => {
     try {
-      await invoke("disconnect");
-      set({ isConnected: false, connectionName: null });
+      const { connectionId } = get();
+      if (connectionId) {
+        await invoke("disconnect", { connectionId });
+      }
+      set({ connectionId: null });
+      await get().checkConnectionStatus();
     } catch (error) {
       set({ error: String(error) });
     }
@@ -177,13 +201,27 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
         username: saved.username,
         password,
         ssl_mode: saved.ssl_mode,
+        ssl_root_cert: saved.ssl_root_cert,
+        ssl_cert: saved.ssl_cert,
+        ssl_key: saved.ssl_key,
       };
+      if (saved.ssh) {
+        const sshSecret =
+          (await invoke<string | null>("get_ssh_secret", { id: saved.id })) ??
+          "";
+        config.ssh =
+          saved.ssh.auth_method === "password"
+            ? { ...saved.ssh, password: sshSecret }
+            : { ...saved.ssh, passphrase: sshSecret };
+      }
       await invoke<string>("connect", { config });
       set({
         isConnected: true,
+        connectionId: saved.id,
         connectionName: saved.name,
         isConnecting: false,
       });
+      await get().checkConnectionStatus();
     } catch (error) {
       set({ isConnecting: false, error: String(error) });
       throw error;
@@ -193,3 +231,15 @@ export const useConnectionStore = create<ConnectionState>((set, get) => ({
   setShouldShowConnectionDialog: (show: boolean) =>
     set({ shouldShowConnectionDialog: show }),
 }));
+
+/**
+ * Returns the id of the connection that commands should target.
+ * Throws when no connection is open.
+ */
+export function requireConnectionId(): string {
+  const { connectionId } = useConnectionStore.getState();
+  if (!connectionId) {
+    throw new Error("Not connected");
+  }
+  return connectionId;
+}
@@ -42,6 +42,8 @@ describe("queryStore", () => {
           columns: [{ name: "id", data_type: "integer" }],
           rows: [[1]],
           row_count: 1,
+          rows_affected: 1,
+          command_tag: "SELECT 1",
           execution_time_ms: 10,
         },
       });
@@ -165,8 +167,10 @@ describe("queryStore", () => {
             { id: "row3", values: [] },
           ],
           total_count: 3,
+          is_count_estimated: false,
           primary_keys: ["id"],
           has_primary_key: true,
+          kind: "table",
         },
       });
 
@@ -322,8 +326,10 @@ describe("queryStore", () => {
           columns: [],
           rows: [],
           total_count: 0,
+          is_count_estimated: false,
           primary_keys: [],
           has_primary_key: false,
+          kind: "table",
         },
         currentSchema: "public",
         currentTable: "users",
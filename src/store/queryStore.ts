@@ -1,20 +1,30 @@
 import { create } from "zustand";
-import { invoke } from "@tauri-apps/api/core";
+import { Channel, invoke } from "@tauri-apps/api/core";
 import type {
   QueryResult,
+  QueryStreamEvent,
   TableData,
   TableDataRequest,
   RowUpdate,
   RowInsert,
   RowDelete,
+  ChangeSet,
+  ChangeSetResult,
+  ChangePreview,
+  ChangeRecord,
   PendingChange,
+  TransactionStatus,
 } from "../types/query";
+import { requireConnectionId } from "./connectionStore";
+import { useSchemaStore } from "./schemaStore";
 
 export interface QueryHistoryItem {
   id: string;
   query: string;
   executedAt: Date;
   rowCount?: number;
+  rowsAffected?: number;
+  commandTag?: string;
   executionTimeMs?: number;
   error?: string;
 }
@@ -25,6 +35,8 @@ interface SerializedHistoryItem {
   query: string;
   executed_at: string;
   row_count?: number;
+  rows_affected?: number;
+  command_tag?: string;
   execution_time_ms?: number;
   error?: string;
 }
@@ -33,7 +45,9 @@ interface QueryState {
   query: string;
   result: QueryResult | null;
   isExecuting: boolean;
+  runningQueryId: string | null;
   error: string | null;
+  transactionStatus: TransactionStatus;
 
   // Query history
   queryHistory: QueryHistoryItem[];
@@ -51,9 +65,16 @@ interface QueryState {
   currentPage: number;
   pageSize: number;
   isLoading: boolean;
+  /** Query id of a running exact row count, for cancelling it */
+  countQueryId: string | null;
 
   setQuery: (query: string) => void;
   executeQuery: () => Promise<void>;
+  cancelQuery: () => Promise<void>;
+  refreshTransactionStatus: () => Promise<void>;
+  beginTransaction: () => Promise<void>;
+  commitTransaction: () => Promise<void>;
+  rollbackTransaction: () => Promise<void>;
   clearResult: () => void;
   clearError: () => void;
   clearHistory: () => void;
@@ -62,9 +83,11 @@ interface QueryState {
 
   // CRUD actions
   loadTableData: (schema: string, table: string) => Promise<void>;
-  refreshTableData: () => Promise<void>;
+  refreshTableData: (options?: { keepCount?: boolean }) => Promise<void>;
   setPage: (page: number) => void;
   setPageSize: (size: number) => void;
+  countRowsExactly: () => Promise<void>;
+  cancelRowCount: () => Promise<void>;
   exitCrudMode: () => void;
 
   // Editing actions
@@ -83,6 +106,8 @@ interface QueryState {
 
   // Save/Discard actions
   saveChanges: () => Promise<void>;
+  previewChanges: () => Promise<ChangePreview | null>;
+  undoLastChange: () => Promise<void>;
   discardChanges: () => void;
   hasPendingChanges: () => boolean;
 }
@@ -91,7 +116,9 @@ export const useQueryStore = create<QueryState>((set, get) => ({
   query: "",
   result: null,
   isExecuting: false,
+  runningQueryId: null,
   error: null,
+  transactionStatus: "idle",
 
   // Query history
   queryHistory: [],
@@ -109,6 +136,7 @@ export const useQueryStore = create<QueryState>((set, get) => ({
   currentPage: 0,
   pageSize: 50,
   isLoading: false,
+  countQueryId: null,
 
   setQuery: (query: string) => set({ query }),
 
@@ -123,11 +151,57 @@ export const useQueryStore = create<QueryState>((set, get) => ({
     };
 
     try {
-      set({ isExecuting: true, error: null, isCrudMode: false });
-      const result = await invoke<QueryResult>("execute_query", { query });
+      set({
+        isExecuting: true,
+        runningQueryId: historyItem.id,
+        error: null,
+        isCrudMode: false,
+      });
+      // Rows arrive in batches and are shown as they come in
+      let result: QueryResult = {
+        columns: [],
+        rows: [],
+        row_count: 0,
+        rows_affected: 0,
+        execution_time_ms: 0,
+      };
+      let onFinished = () => {};
+      const finished = new Promise<void>((resolve) => {
+        onFinished = resolve;
+      });
+      const onEvent = new Channel<QueryStreamEvent>();
+      onEvent.onmessage = (message) => {
+        switch (message.event) {
+          case "columns":
+            result = { ...result, columns: message.data.columns };
+            break;
+          case "rows":
+            result = {
+              ...result,
+              rows: result.rows.concat(message.data.rows),
+              row_count: message.data.rows_sent,
+            };
+            break;
+          case "finished":
+            result = { ...result, ...message.data };
+            onFinished();
+            break;
+        }
+        set({ result, tableData: null });
+      };
+      await invoke("execute_query_stream", {
+        connectionId: requireConnectionId(),
+        queryId: historyItem.id,
+        query,
+        onEvent,
+      });
+      // Channel messages may still be in flight when the command returns
+      await finished;
 
       // Update history with success info
       historyItem.rowCount = result.row_count;
+      historyItem.rowsAffected = result.rows_affected;
+      historyItem.commandTag = result.command_tag;
       historyItem.executionTimeMs = result.execution_time_ms;
 
       // Add to history (keep last 50)
@@ -136,12 +210,19 @@ export const useQueryStore = create<QueryState>((set, get) => ({
       set({
         result,
         isExecuting: false,
+        runningQueryId: null,
         tableData: null,
         queryHistory: newHistory,
       });
 
+      // Reload the schema tree after DDL
+      if (/^(CREATE|ALTER|DROP)\b/.test(result.command_tag ?? "")) {
+        useSchemaStore.getState().fetchSchemas();
+      }
+
       // Persist history
       await saveHistory();
+      await get().refreshTransactionStatus();
     } catch (error) {
       // Update history with error
       historyItem.error = String(error);
@@ -149,12 +230,73 @@ export const useQueryStore = create<QueryState>((set, get) => ({
 
       set({
         isExecuting: false,
+        runningQueryId: null,
         error: String(error),
         queryHistory: newHistory,
       });
 
       // Persist history even on error
       await saveHistory();
+      await get().refreshTransactionStatus();
+    }
+  },
+
+  cancelQuery: async () => {
+    const { runningQueryId } = get();
+    if (!runningQueryId) return;
+
+    try {
+      await invoke<boolean>("cancel_query", { queryId: runningQueryId });
+    } catch (error) {
+      console.error("Failed to cancel query:", error);
+    }
+  },
+
+  refreshTransactionStatus: async () => {
+    try {
+      const transactionStatus = await invoke<TransactionStatus>(
+        "get_transaction_status",
+        { connectionId: requireConnectionId() }
+      );
+      set({ transactionStatus });
+    } catch (error) {
+      console.error("Failed to get transaction status:", error);
+    }
+  },
+
+  beginTransaction: async () => {
+    try {
+      const transactionStatus = await invoke<TransactionStatus>(
+        "begin_transaction",
+        { connectionId: requireConnectionId() }
+      );
+      set({ transactionStatus, error: null });
+    } catch (error) {
+      set({ error: String(error) });
+    }
+  },
+
+  commitTransaction: async () => {
+    try {
+      const transactionStatus = await invoke<TransactionStatus>(
+        "commit_transaction",
+        { connectionId: requireConnectionId() }
+      );
+      set({ transactionStatus, error: null });
+    } catch (error) {
+      set({ error: String(error) });
+    }
+  },
+
+  rollbackTransaction: async () => {
+    try {
+      const transactionStatus = await invoke<TransactionStatus>(
+        "rollback_transaction",
+        { connectionId: requireConnectionId() }
+      );
+      set({ transactionStatus, error: null });
+    } catch (error) {
+      set({ error: String(error) });
     }
   },
 
@@ -175,6 +317,8 @@ export const useQueryStore = create<QueryState>((set, get) => ({
         query: item.query,
         executedAt: new Date(item.executed_at),
         rowCount: item.row_count,
+        rowsAffected: item.rows_affected,
+        commandTag: item.command_tag,
         executionTimeMs: item.execution_time_ms,
         error: item.error,
       }));
@@ -192,6 +336,8 @@ export const useQueryStore = create<QueryState>((set, get) => ({
         query: item.query,
         executed_at: item.executedAt.toISOString(),
         row_count: item.rowCount,
+        rows_affected: item.rowsAffected,
+        command_tag: item.commandTag,
         execution_time_ms: item.executionTimeMs,
         error: item.error,
       }));
@@ -220,7 +366,10 @@ export const useQueryStore = create<QueryState>((set, get) => ({
         offset: currentPage * pageSize,
       };
 
-      const tableData = await invoke<TableData>("get_table_data", { request });
+      const tableData = await invoke<TableData>("get_table_data", {
+        connectionId: requireConnectionId(),
+        request,
+      });
 
       set({
         tableData,
@@ -238,8 +387,9 @@ export const useQueryStore = create<QueryState>((set, get) => ({
     }
   },
 
-  refreshTableData: async () => {
-    const { currentSchema, currentTable, pageSize, currentPage } = get();
+  refreshTableData: async (options) => {
+    const { currentSchema, currentTable, pageSize, currentPage, tableData } =
+      get();
     if (!currentSchema || !currentTable) return;
 
     try {
@@ -252,10 +402,22 @@ export const useQueryStore = create<QueryState>((set, get) => ({
         offset: currentPage * pageSize,
       };
 
-      const tableData = await invoke<TableData>("get_table_data", { request });
+      const nextData = await invoke<TableData>("get_table_data", {
+        connectionId: requireConnectionId(),
+        request,
+      });
 
+      // Paging keeps the count, which may have been counted exactly; edits
+      // and reloads estimate it again
       set({
-        tableData,
+        tableData:
+          options?.keepCount && tableData
+            ? {
+                ...nextData,
+                total_count: tableData.total_count,
+                is_count_estimated: tableData.is_count_estimated,
+              }
+            : nextData,
         isLoading: false,
         pendingChanges: new Map(),
         pendingInserts: [],
@@ -268,14 +430,61 @@ export const useQueryStore = create<QueryState>((set, get) => ({
     }
   },
 
+  countRowsExactly: async () => {
+    const { currentSchema, currentTable } = get();
+    if (!currentSchema || !currentTable) return;
+
+    const queryId = crypto.randomUUID();
+    try {
+      set({ countQueryId: queryId, error: null });
+      const count = await invoke<number>("count_table_rows", {
+        connectionId: requireConnectionId(),
+        queryId,
+        schema: currentSchema,
+        table: currentTable,
+      });
+
+      // Ignore the count if another table was opened meanwhile
+      const state = get();
+      if (
+        state.tableData &&
+        state.currentSchema === currentSchema &&
+        state.currentTable === currentTable
+      ) {
+        set({
+          tableData: {
+            ...state.tableData,
+            total_count: count,
+            is_count_estimated: false,
+          },
+        });
+      }
+      set({ countQueryId: null });
+    } catch (error) {
+      const cancelled = String(error).startsWith("Query cancelled");
+      set({ countQueryId: null, error: cancelled ? null : String(error) });
+    }
+  },
+
+  cancelRowCount: async () => {
+    const { countQueryId } = get();
+    if (!countQueryId) return;
+
+    try {
+      await invoke<boolean>("cancel_query", { queryId: countQueryId });
+    } catch (error) {
+      console.error("Failed to cancel row count:", error);
+    }
+  },
+
   setPage: (page: number) => {
     set({ currentPage: page });
-    get().refreshTableData();
+    get().refreshTableData({ keepCount: true });
   },
 
   setPageSize: (size: number) => {
     set({ pageSize: size, currentPage: 0 });
-    get().refreshTableData();
+    get().refreshTableData({ keepCount: true });
   },
 
   exitCrudMode: () => {
@@ -351,57 +560,25 @@ export const useQueryStore = create<QueryState>((set, get) => ({
 
   // Save/Discard actions
   saveChanges: async () => {
-    const {
-      currentSchema,
-      currentTable,
-      pendingChanges,
-      pendingInserts,
-      pendingDeletes,
-    } = get();
-
-    if (!currentSchema || !currentTable) return;
+    const changes = pendingChangeSet(get());
+    if (!changes) return;
 
     try {
       set({ isLoading: true, error: null });
 
-      // Process updates
-      if (pendingChanges.size > 0) {
-        const updates: RowUpdate[] = Array.from(pendingChanges.values())
-          .filter((c) => c.type === "update")
-          .map((c) => ({
-            row_id: c.rowId,
-            column: c.column!,
-            new_value: c.newValue,
-          }));
-
-        if (updates.length > 0) {
-          await invoke("update_rows", {
-            schema: currentSchema,
-            table: currentTable,
-            updates,
-          });
-        }
-      }
-
-      // Process inserts
-      if (pendingInserts.length > 0) {
-        await invoke("insert_rows", {
-          schema: currentSchema,
-          table: currentTable,
-          rows: pendingInserts,
-        });
-      }
+      // All edits are applied in one transaction; nothing is saved if any fails
+      const result = await invoke<ChangeSetResult>("apply_changes", {
+        connectionId: requireConnectionId(),
+        changes,
+      });
 
-      // Process deletes
-      if (pendingDeletes.size > 0) {
-        const deletes: RowDelete[] = Array.from(pendingDeletes).map((id) => ({
-          row_id: id,
-        }));
-        await invoke("delete_rows", {
-          schema: currentSchema,
-          table: currentTable,
-          deletes,
-        });
+      if (!result.committed) {
+        const messages = result.errors.map(
+          (e) =>
+            `${e.kind} #${e.index + 1}${e.column ? ` (${e.column})` : ""}: ${e.message}`,
+        );
+        set({ isLoading: false, error: messages.join("\n") });
+        return;
       }
 
       // Refresh data
@@ -411,6 +588,35 @@ export const useQueryStore = create<QueryState>((set, get) => ({
     }
   },
 
+  previewChanges: async () => {
+    const changes = pendingChangeSet(get());
+    if (!changes) return null;
+
+    try {
+      return await invoke<ChangePreview>("preview_changes", {
+        connectionId: requireConnectionId(),
+        changes,
+      });
+    } catch (error) {
+      set({ error: String(error) });
+      return null;
+    }
+  },
+
+  undoLastChange: async () => {
+    try {
+      set({ isLoading: true, error: null });
+      // Reverts the last applied edit on this connection, whichever table
+      // it was made to
+      await invoke<ChangeRecord>("undo_last_change", {
+        connectionId: requireConnectionId(),
+      });
+      await get().refreshTableData();
+    } catch (error) {
+      set({ isLoading: false, error: String(error) });
+    }
+  },
+
   discardChanges: () => {
     set({
       pendingChanges: new Map(),
@@ -429,3 +635,43 @@ export const useQueryStore = create<QueryState>((set, get) => ({
     );
   },
 }));
+
+/** Collects the pending grid edits into the change set they're saved as. */
+function pendingChangeSet(state: QueryState): ChangeSet | null {
+  const {
+    currentSchema,
+    currentTable,
+    pendingChanges,
+    pendingInserts,
+    pendingDeletes,
+    tableData,
+  } = state;
+
+  if (!currentSchema || !currentTable) return null;
+
+  // Row versions as loaded, so edits to rows changed since then conflict
+  const xmins = new Map(
+    (tableData?.rows ?? []).map((r) => [r.id, r.xmin ?? undefined]),
+  );
+
+  const updates: RowUpdate[] = Array.from(pendingChanges.values())
+    .filter((c) => c.type === "update")
+    .map((c) => ({
+      row_id: c.rowId,
+      column: c.column!,
+      new_value: c.newValue,
+      xmin: xmins.get(c.rowId),
+    }));
+  const deletes: RowDelete[] = Array.from(pendingDeletes).map((id) => ({
+    row_id: id,
+    xmin: xmins.get(id),
+  }));
+
+  return {
+    schema: currentSchema,
+    table: currentTable,
+    inserts: pendingInserts,
+    updates,
+    deletes,
+  };
+}
@@ -1,6 +1,7 @@
 import { create } from "zustand";
 import { invoke } from "@tauri-apps/api/core";
-import type { SchemaInfo } from "../types/schema";
+import type { ColumnInfo, SchemaInfo, TableInfo } from "../types/schema";
+import { requireConnectionId } from "./connectionStore";
 
 interface FocusedTable {
   schema: string;
@@ -8,37 +9,183 @@ interface FocusedTable {
 }
 
 interface SchemaState {
+  /** Schemas as loaded so far; tables and columns are filled in on demand */
   schemas: SchemaInfo[];
+  /** Schemas whose tables have been loaded */
+  loadedSchemas: Record<string, boolean>;
+  /** Tables whose columns have been loaded, keyed by `schema.table` */
+  loadedTables: Record<string, boolean>;
   isLoading: boolean;
   error: string | null;
   focusedTable: FocusedTable | null;
   fetchSchemas: () => Promise<void>;
+  refreshSchemas: () => Promise<void>;
+  fetchTables: (schema: string) => Promise<void>;
+  fetchColumns: (schema: string, table: string) => Promise<void>;
+  fetchSchemaDetails: (schemaNames?: string[]) => Promise<void>;
   clearSchemas: () => void;
   setFocusedTable: (schema: string, table: string) => void;
   clearFocusedTable: () => void;
 }
 
+export const tableKey = (schema: string, table: string) =>
+  `${schema}.${table}`;
+
+// Complete schema loads in progress, so repeated requests wait for them
+// instead of loading the same schemas again
+let pendingDetails: Promise<void> | null = null;
+
+function errorMessage(error: unknown): string {
+  return error instanceof Error ? error.message : String(error);
+}
+
 export const useSchemaStore = create<SchemaState>((set, get) => ({
   schemas: [],
+  loadedSchemas: {},
+  loadedTables: {},
   isLoading: false,
   error: null,
   focusedTable: null,
 
+  // Loads schema names only; tables and columns load as the tree expands
   fetchSchemas: async () => {
     set({ isLoading: true, error: null });
     try {
-      const schemas = await invoke<SchemaInfo[]>("get_schemas");
-      set({ schemas, isLoading: false });
-    } catch (error) {
+      const names = await invoke<string[]>("get_schema_names", {
+        connectionId: requireConnectionId(),
+      });
       set({
-        error: error instanceof Error ? error.message : String(error),
+        schemas: names.map((name) => ({ name, tables: [] })),
+        loadedSchemas: {},
+        loadedTables: {},
         isLoading: false,
       });
+    } catch (error) {
+      set({ error: errorMessage(error), isLoading: false });
+    }
+  },
+
+  // Drops the backend's cached metadata, so changes made elsewhere show at once
+  refreshSchemas: async () => {
+    try {
+      await invoke("refresh_schema", { connectionId: requireConnectionId() });
+    } catch (error) {
+      set({ error: errorMessage(error) });
+      return;
+    }
+    await get().fetchSchemas();
+  },
+
+  fetchTables: async (schema: string) => {
+    if (get().loadedSchemas[schema]) return;
+    try {
+      const tables = await invoke<TableInfo[]>("get_tables", {
+        connectionId: requireConnectionId(),
+        schemaName: schema,
+      });
+      set((state) => ({
+        schemas: state.schemas.map((s) =>
+          s.name === schema ? { ...s, tables } : s
+        ),
+        loadedSchemas: { ...state.loadedSchemas, [schema]: true },
+      }));
+    } catch (error) {
+      set({ error: errorMessage(error) });
+    }
+  },
+
+  fetchColumns: async (schema: string, table: string) => {
+    const key = tableKey(schema, table);
+    if (get().loadedTables[key]) return;
+    try {
+      const columns = await invoke<ColumnInfo[]>("get_columns", {
+        connectionId: requireConnectionId(),
+        schemaName: schema,
+        tableName: table,
+      });
+      set((state) => ({
+        schemas: state.schemas.map((s) =>
+          s.name === schema
+            ? {
+                ...s,
+                tables: s.tables.map((t) =>
+                  t.name === table ? { ...t, columns } : t
+                ),
+              }
+            : s
+        ),
+        loadedTables: { ...state.loadedTables, [key]: true },
+      }));
+    } catch (error) {
+      set({ error: errorMessage(error) });
+    }
+  },
+
+  // Loads schemas completely, with every table's columns, for views that
+  // need the whole schema (ER diagram, AI context). Loads every schema if
+  // no names are given.
+  fetchSchemaDetails: async (schemaNames?: string[]) => {
+    while (pendingDetails) {
+      await pendingDetails;
+    }
+    const { schemas, loadedSchemas, loadedTables } = get();
+    const isComplete = (schema: SchemaInfo) =>
+      loadedSchemas[schema.name] &&
+      schema.tables.every((t) => loadedTables[tableKey(schema.name, t.name)]);
+    const pending = (schemaNames ?? schemas.map((s) => s.name)).filter(
+      (name) => {
+        const schema = schemas.find((s) => s.name === name);
+        return !schema || !isComplete(schema);
+      }
+    );
+    if (pending.length === 0) return;
+
+    try {
+      const request = invoke<SchemaInfo[]>("get_schemas", {
+        connectionId: requireConnectionId(),
+        schemaNames: pending,
+      });
+      pendingDetails = request.then(
+        () => undefined,
+        () => undefined
+      );
+      const details = await request;
+      set((state) => {
+        const loadedSchemas = { ...state.loadedSchemas };
+        const loadedTables = { ...state.loadedTables };
+        for (const schema of details) {
+          loadedSchemas[schema.name] = true;
+          for (const table of schema.tables) {
+            loadedTables[tableKey(schema.name, table.name)] = true;
+          }
+        }
+        const known = new Set(state.schemas.map((s) => s.name));
+        return {
+          schemas: [
+            ...state.schemas.map(
+              (s) => details.find((d) => d.name === s.name) ?? s
+            ),
+            ...details.filter((d) => !known.has(d.name)),
+          ],
+          loadedSchemas,
+          loadedTables,
+        };
+      });
+    } catch (error) {
+      set({ error: errorMessage(error) });
+    } finally {
+      pendingDetails = null;
     }
   },
 
   clearSchemas: () => {
-    set({ schemas: [], error: null, focusedTable: null });
+    set({
+      schemas: [],
+      loadedSchemas: {},
+      loadedTables: {},
+      error: null,
+      focusedTable: null,
+    });
   },
 
   setFocusedTable: (schema: string, table: string) => {
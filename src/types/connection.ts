@@ -1,5 +1,7 @@
 // Re-export generated types from ts-rs
 export type {
+  ActiveConnection,
+  SshAuthMethod,
   SslMode,
   SavedConnection,
   SaveConnectionInput,
@@ -7,8 +9,18 @@ export type {
 
 // ConnectionConfig with password field (not exported from Rust for security)
 // The generated ConnectionConfig doesn't include password, so we extend it
-import type { ConnectionConfig as BaseConnectionConfig } from "./generated";
+import type {
+  ConnectionConfig as BaseConnectionConfig,
+  SshTunnelConfig as BaseSshTunnelConfig,
+} from "./generated";
+
+// SSH secrets are kept in the keyring and skipped by ts-rs as well
+export interface SshTunnelConfig extends BaseSshTunnelConfig {
+  password?: string;
+  passphrase?: string;
+}
 
 export interface ConnectionConfig extends BaseConnectionConfig {
   password: string;
+  ssh?: SshTunnelConfig;
 }
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An open database session, as listed by `get_connection_status`
+ */
+export type ActiveConnection = {
+  id: string;
+  name: string;
+  host: string;
+  port: number;
+  database: string;
+};
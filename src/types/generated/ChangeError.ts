@@ -0,0 +1,21 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeKind } from "./ChangeKind";
+import type { TableRow } from "./TableRow";
+
+/**
+ * A failed edit, identified by its kind and position in the change set
+ */
+export type ChangeError = {
+  kind: ChangeKind;
+  index: number;
+  row_id?: string;
+  /**
+   * The column whose value was rejected, if the error is about a value
+   */
+  column?: string;
+  message: string;
+  /**
+   * The row as it is now, when the edit conflicts with a concurrent change
+   */
+  current_row?: TableRow;
+};
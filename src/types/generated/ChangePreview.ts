@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StatementPreview } from "./StatementPreview";
+
+/**
+ * The statements a change set would run, in order, without running them
+ */
+export type ChangePreview = {
+  statements: Array<StatementPreview>;
+  /**
+   * Every statement with literals inlined, one per line, ending in `;`
+   */
+  script: string;
+};
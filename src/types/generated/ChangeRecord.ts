@@ -0,0 +1,14 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * An applied edit that can be undone
+ */
+export type ChangeRecord = {
+  id: number;
+  schema: string;
+  table: string;
+  applied_at: string;
+  inserted: number;
+  updated: number;
+  deleted: number;
+};
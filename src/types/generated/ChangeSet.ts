@@ -0,0 +1,15 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RowDelete } from "./RowDelete";
+import type { RowInsert } from "./RowInsert";
+import type { RowUpdate } from "./RowUpdate";
+
+/**
+ * Grid edits for one table, applied together in a single transaction
+ */
+export type ChangeSet = {
+  schema: string;
+  table: string;
+  inserts: Array<RowInsert>;
+  updates: Array<RowUpdate>;
+  deletes: Array<RowDelete>;
+};
@@ -0,0 +1,18 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeError } from "./ChangeError";
+import type { TableRow } from "./TableRow";
+
+export type ChangeSetResult = {
+  /**
+   * False if any edit failed, in which case none of them were applied
+   */
+  committed: boolean;
+  inserted: number;
+  updated: number;
+  deleted: number;
+  /**
+   * Inserted rows as stored, with generated values and their row ids
+   */
+  inserted_rows: Array<TableRow>;
+  errors: Array<ChangeError>;
+};
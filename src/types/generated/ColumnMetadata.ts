@@ -1,3 +1,15 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type ColumnMetadata = { name: string; data_type: string };
+export type ColumnMetadata = {
+  name: string;
+  data_type: string;
+  /**
+   * PostgreSQL type OID
+   */
+  type_oid?: number;
+  /**
+   * Whether the column may contain NULL, known only for columns read
+   * directly from a table
+   */
+  nullable?: boolean;
+};
@@ -0,0 +1,12 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type FilterOperator =
+  | "eq"
+  | "not_eq"
+  | "lt"
+  | "gt"
+  | "like"
+  | "ilike"
+  | "in"
+  | "is_null"
+  | "between";
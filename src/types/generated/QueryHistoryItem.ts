@@ -5,6 +5,8 @@ export type QueryHistoryItem = {
   query: string;
   executed_at: string;
   row_count?: number;
+  rows_affected?: number;
+  command_tag?: string;
   execution_time_ms?: number;
   error?: string;
 };
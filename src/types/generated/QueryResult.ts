@@ -5,5 +5,13 @@ export type QueryResult = {
   columns: Array<ColumnMetadata>;
   rows: unknown[][];
   row_count: number;
+  /**
+   * Rows inserted, updated or deleted; equals `row_count` for a `SELECT`
+   */
+  rows_affected: number;
+  /**
+   * Command tag such as `UPDATE 42` or `CREATE TABLE`
+   */
+  command_tag?: string;
   execution_time_ms: number;
 };
@@ -0,0 +1,27 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ColumnMetadata } from "./ColumnMetadata";
+
+/**
+ * Messages sent over the channel of `execute_query_stream`
+ */
+export type QueryStreamEvent =
+  | { event: "columns"; data: { columns: Array<ColumnMetadata> } }
+  | {
+      event: "rows";
+      data: {
+        rows: unknown[][];
+        /**
+         * Total rows sent so far, including this batch
+         */
+        rows_sent: number;
+      };
+    }
+  | {
+      event: "finished";
+      data: {
+        row_count: number;
+        rows_affected: number;
+        command_tag?: string;
+        execution_time_ms: number;
+      };
+    };
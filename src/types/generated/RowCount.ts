@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Number of rows in a table, possibly estimated
+ */
+export type RowCount = { count: number; is_estimated: boolean };
@@ -1,3 +1,14 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type RowDelete = { row_id: string };
+export type RowDelete = {
+  row_id: string;
+  /**
+   * `TableRow.xmin` as loaded; the delete conflicts if the row has been
+   * written since
+   */
+  xmin?: string;
+  /**
+   * Column values as loaded; the delete conflicts if any of them differ
+   */
+  original_values?: Record<string, unknown>;
+};
@@ -0,0 +1,6 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Strategy used to identify rows, recorded in each `TableRow.id`
+ */
+export type RowKeyKind = "primary_key" | "unique_index" | "ctid";
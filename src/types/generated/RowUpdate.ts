@@ -1,3 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type RowUpdate = { row_id: string; column: string; new_value: unknown };
+export type RowUpdate = {
+  row_id: string;
+  column: string;
+  new_value: unknown;
+  /**
+   * `TableRow.xmin` as loaded; the update conflicts if the row has been
+   * written since
+   */
+  xmin?: string;
+  /**
+   * Column values as loaded; the update conflicts if any of them differ
+   */
+  original_values?: Record<string, unknown>;
+};
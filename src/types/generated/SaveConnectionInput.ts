@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SshTunnelConfig } from "./SshTunnelConfig";
 import type { SslMode } from "./SslMode";
 
 export type SaveConnectionInput = {
@@ -10,5 +11,18 @@ export type SaveConnectionInput = {
   username: string;
   password: string;
   ssl_mode: SslMode;
+  /**
+   * Path to the root CA certificate (libpq `sslrootcert`)
+   */
+  ssl_root_cert?: string;
+  /**
+   * Path to the client certificate (libpq `sslcert`)
+   */
+  ssl_cert?: string;
+  /**
+   * Path to the client certificate's private key (libpq `sslkey`)
+   */
+  ssl_key?: string;
+  ssh?: SshTunnelConfig;
   is_default: boolean;
 };
@@ -1,4 +1,5 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SshTunnelConfig } from "./SshTunnelConfig";
 import type { SslMode } from "./SslMode";
 
 export type SavedConnection = {
@@ -9,5 +10,18 @@ export type SavedConnection = {
   database: string;
   username: string;
   ssl_mode: SslMode;
+  /**
+   * Path to the root CA certificate (libpq `sslrootcert`)
+   */
+  ssl_root_cert?: string;
+  /**
+   * Path to the client certificate (libpq `sslcert`)
+   */
+  ssl_cert?: string;
+  /**
+   * Path to the client certificate's private key (libpq `sslkey`)
+   */
+  ssl_key?: string;
+  ssh?: SshTunnelConfig;
   is_default: boolean;
 };
@@ -0,0 +1,17 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Options for `execute_script`
+ */
+export type ScriptOptions = {
+  /**
+   * Skip the remaining statements after the first error
+   */
+  stop_on_error: boolean;
+  /**
+   * Run the whole script in a single transaction. With `stop_on_error`
+   * an error rolls back every statement; otherwise only the failing
+   * statement is rolled back and the rest is committed.
+   */
+  use_transaction: boolean;
+};
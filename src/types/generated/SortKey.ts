@@ -0,0 +1,13 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { NullsOrder } from "./NullsOrder";
+import type { SortDirection } from "./SortDirection";
+
+export type SortKey = {
+  column: string;
+  direction?: SortDirection;
+  /**
+   * Where NULLs go; PostgreSQL's default puts them last when ascending
+   * and first when descending
+   */
+  nulls?: NullsOrder;
+};
@@ -0,0 +1,16 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SshAuthMethod } from "./SshAuthMethod";
+
+/**
+ * SSH jump host used to reach a database that is not directly exposed.
+ *
+ * Secrets are never serialized; they live in the keyring next to the
+ * database password.
+ */
+export type SshTunnelConfig = {
+  host: string;
+  port: number;
+  username: string;
+  auth_method: SshAuthMethod;
+  private_key_path?: string;
+};
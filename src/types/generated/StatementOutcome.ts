@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { QueryResult } from "./QueryResult";
+
+export type StatementOutcome =
+  | { kind: "result_set"; result: QueryResult }
+  | { kind: "rows_affected"; rows_affected: number; command_tag?: string }
+  | { kind: "error"; message: string }
+  | { kind: "skipped" };
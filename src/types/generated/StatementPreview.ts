@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { ChangeKind } from "./ChangeKind";
+
+export type StatementPreview = {
+  kind: ChangeKind;
+  /**
+   * Indexes of the edits the statement applies; an insert may cover
+   * several rows
+   */
+  indexes: Array<number>;
+  /**
+   * The statement with its values inlined as quoted literals
+   */
+  sql: string;
+  /**
+   * The statement as run, with `$n` placeholders
+   */
+  parameterized_sql: string;
+  /**
+   * Text of each placeholder's value, `null` for NULL
+   */
+  params: Array<string | null>;
+};
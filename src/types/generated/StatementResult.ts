@@ -0,0 +1,8 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { StatementOutcome } from "./StatementOutcome";
+
+export type StatementResult = {
+  statement: string;
+  outcome: StatementOutcome;
+  execution_time_ms: number;
+};
@@ -3,6 +3,10 @@
 export type TableColumnInfo = {
   name: string;
   data_type: string;
+  /**
+   * Type name usable in a cast, e.g. `numeric`, `public.mood` or `text[]`
+   */
+  sql_type: string;
   is_nullable: boolean;
   is_primary_key: boolean;
   is_auto_generated: boolean;
@@ -1,4 +1,6 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { RelationKind } from "./RelationKind";
+import type { RowKeyKind } from "./RowKeyKind";
 import type { TableColumnInfo } from "./TableColumnInfo";
 import type { TableRow } from "./TableRow";
 
@@ -6,6 +8,29 @@ export type TableData = {
   columns: Array<TableColumnInfo>;
   rows: Array<TableRow>;
   total_count: number;
+  /**
+   * True if `total_count` is the planner's estimate; pages are loaded
+   * with one, and an exact count is asked for separately
+   */
+  is_count_estimated: boolean;
   primary_keys: Array<string>;
   has_primary_key: boolean;
+  /**
+   * Kind of relation browsed; only tables can be edited
+   */
+  kind: RelationKind;
+  /**
+   * How rows are identified for editing; `None` if they can't be edited
+   */
+  row_key?: RowKeyKind;
+  /**
+   * Cursor for the following page; `None` on the last page or when the
+   * table has no unique key to seek on
+   */
+  next_cursor?: string;
+  /**
+   * Cursor for the preceding page; `None` on the first page or when the
+   * table has no unique key to seek on
+   */
+  prev_cursor?: string;
 };
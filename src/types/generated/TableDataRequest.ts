@@ -1,8 +1,24 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { SortKey } from "./SortKey";
+import type { TableFilter } from "./TableFilter";
 
 export type TableDataRequest = {
   schema: string;
   table: string;
   limit: number;
   offset: number;
+  /**
+   * Only return rows matching this filter; also applies to `total_count`
+   */
+  filter?: TableFilter;
+  /**
+   * Sort keys in priority order; the table's primary key or unique key is
+   * appended as a tie-breaker, and is the whole order when this is empty
+   */
+  sort?: Array<SortKey>;
+  /**
+   * Cursor from a previous page's `next_cursor` or `prev_cursor`; when
+   * set, `offset` is ignored and rows are found by seeking on the sort key
+   */
+  cursor?: string;
 };
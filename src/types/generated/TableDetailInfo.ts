@@ -3,10 +3,12 @@ import type { ColumnInfo } from "./ColumnInfo";
 import type { ConstraintInfo } from "./ConstraintInfo";
 import type { ForeignKeyInfo } from "./ForeignKeyInfo";
 import type { IndexInfo } from "./IndexInfo";
+import type { RelationKind } from "./RelationKind";
 
 export type TableDetailInfo = {
   schema: string;
   name: string;
+  kind: RelationKind;
   columns: Array<ColumnInfo>;
   indexes: Array<IndexInfo>;
   constraints: Array<ConstraintInfo>;
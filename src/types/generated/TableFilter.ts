@@ -0,0 +1,23 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { FilterCombinator } from "./FilterCombinator";
+import type { FilterOperator } from "./FilterOperator";
+
+/**
+ * Row filter for table data, compiled into a parameterized `WHERE` clause
+ */
+export type TableFilter =
+  | {
+      kind: "condition";
+      column: string;
+      operator: FilterOperator;
+      /**
+       * A single value, an array for `in`, or `[low, high]` for
+       * `between`; ignored by `is_null`
+       */
+      value: unknown;
+    }
+  | {
+      kind: "group";
+      combinator: FilterCombinator;
+      filters: Array<TableFilter>;
+    };
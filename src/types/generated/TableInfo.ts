@@ -1,8 +1,18 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { ColumnInfo } from "./ColumnInfo";
+import type { RelationKind } from "./RelationKind";
 
 export type TableInfo = {
   schema: string;
   name: string;
+  kind: RelationKind;
+  /**
+   * Schema of the partitioned table this is a partition of
+   */
+  parent_schema?: string;
+  /**
+   * Name of the partitioned table this is a partition of
+   */
+  parent_table?: string;
   columns: Array<ColumnInfo>;
 };
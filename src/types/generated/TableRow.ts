@@ -1,3 +1,10 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
-export type TableRow = { id: string; values: unknown[] };
+export type TableRow = {
+  id: string;
+  values: unknown[];
+  /**
+   * Row version (`xmin`) when loaded, for optimistic concurrency checks
+   */
+  xmin?: string;
+};
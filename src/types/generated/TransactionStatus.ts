@@ -0,0 +1,10 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+/**
+ * Transaction state of a connection's SQL editor session
+ */
+export type TransactionStatus =
+  | "idle"
+  | "in_transaction"
+  | "failed"
+  | "unknown";
@@ -5,30 +5,54 @@ export type { AiProvider } from "./AiProvider";
 export type { GenerateSqlRequest } from "./GenerateSqlRequest";
 
 // Connection types
+export type { ActiveConnection } from "./ActiveConnection";
 export type { ConnectionConfig } from "./ConnectionConfig";
 export type { SavedConnection } from "./SavedConnection";
 export type { SaveConnectionInput } from "./SaveConnectionInput";
+export type { SshAuthMethod } from "./SshAuthMethod";
+export type { SshTunnelConfig } from "./SshTunnelConfig";
 export type { SslMode } from "./SslMode";
 
 // Query types
 export type { ColumnMetadata } from "./ColumnMetadata";
 export type { QueryResult } from "./QueryResult";
+export type { QueryStreamEvent } from "./QueryStreamEvent";
 export type { QueryHistoryItem } from "./QueryHistoryItem";
+export type { ScriptOptions } from "./ScriptOptions";
+export type { StatementOutcome } from "./StatementOutcome";
+export type { StatementResult } from "./StatementResult";
+export type { TransactionStatus } from "./TransactionStatus";
 
 // Schema types
 export type { ColumnInfo } from "./ColumnInfo";
 export type { ConstraintInfo } from "./ConstraintInfo";
 export type { ForeignKeyInfo } from "./ForeignKeyInfo";
 export type { IndexInfo } from "./IndexInfo";
+export type { RelationKind } from "./RelationKind";
 export type { SchemaInfo } from "./SchemaInfo";
 export type { TableDetailInfo } from "./TableDetailInfo";
 export type { TableInfo } from "./TableInfo";
 
 // Table data types
 export type { RowDelete } from "./RowDelete";
+export type { ChangeSet } from "./ChangeSet";
+export type { ChangeKind } from "./ChangeKind";
+export type { ChangeError } from "./ChangeError";
+export type { ChangeSetResult } from "./ChangeSetResult";
+export type { ChangePreview } from "./ChangePreview";
+export type { StatementPreview } from "./StatementPreview";
+export type { ChangeRecord } from "./ChangeRecord";
 export type { RowInsert } from "./RowInsert";
 export type { RowUpdate } from "./RowUpdate";
 export type { TableColumnInfo } from "./TableColumnInfo";
 export type { TableData } from "./TableData";
+export type { RowCount } from "./RowCount";
+export type { RowKeyKind } from "./RowKeyKind";
 export type { TableDataRequest } from "./TableDataRequest";
+export type { TableFilter } from "./TableFilter";
+export type { FilterOperator } from "./FilterOperator";
+export type { FilterCombinator } from "./FilterCombinator";
+export type { SortKey } from "./SortKey";
+export type { SortDirection } from "./SortDirection";
+export type { NullsOrder } from "./NullsOrder";
 export type { TableRow } from "./TableRow";
@@ -2,13 +2,30 @@
 export type {
   ColumnMetadata,
   QueryResult,
+  QueryStreamEvent,
   TableColumnInfo,
   TableRow,
   TableData,
+  RowCount,
+  RowKeyKind,
   TableDataRequest,
+  TableFilter,
+  FilterOperator,
+  FilterCombinator,
+  SortKey,
+  SortDirection,
+  NullsOrder,
   RowUpdate,
   RowInsert,
   RowDelete,
+  ChangeSet,
+  ChangeKind,
+  ChangeError,
+  ChangeSetResult,
+  ChangePreview,
+  StatementPreview,
+  ChangeRecord,
+  TransactionStatus,
 } from "./generated";
 
 // Frontend-only type (not in Rust)
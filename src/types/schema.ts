@@ -1,3 +1,5 @@
+import type { RelationKind } from "./generated";
+
 // Re-export generated types from ts-rs
 export type {
   SchemaInfo,
@@ -7,4 +9,18 @@ export type {
   IndexInfo,
   ConstraintInfo,
   TableDetailInfo,
+  RelationKind,
 } from "./generated";
+
+export const RELATION_KIND_LABELS: Record<RelationKind, string> = {
+  table: "Table",
+  partitioned_table: "Partitioned table",
+  view: "View",
+  materialized_view: "Materialized view",
+  foreign_table: "Foreign table",
+};
+
+/** Whether rows of a relation of this kind can be written */
+export function isWritableKind(kind: RelationKind): boolean {
+  return kind === "table" || kind === "partitioned_table";
+}